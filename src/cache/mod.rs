@@ -0,0 +1,375 @@
+//! Local cache of API responses, stored as JSON in a SQLite database
+
+use crate::{error::Error::CacheError, settings::get_default_cache_dir, Result};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs::create_dir_all,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+pub mod names;
+
+pub const KEY_ACTIVITIES: &str = "activities";
+pub const KEY_TAGS_AND_MENTIONS: &str = "tags_and_mentions";
+pub const KEY_DEFAULT_SPACE: &str = "default_space";
+pub const KEY_TIME_ENTRIES: &str = "time_entries/";
+pub const KEY_TRACKING: &str = "tracking";
+
+const CACHE_FILE_NAME: &str = "cache.sqlite";
+/// Status bars poll the current tracking every few seconds, changes by the CLI invalidate it
+pub const TRACKING_TTL_SECS: i64 = 30;
+
+/// A cached response, `fetched_at` is reset to the epoch when the value is invalidated
+#[derive(Debug)]
+pub struct CacheEntry {
+    pub key: String,
+    pub fetched_at: DateTime<Utc>,
+    pub size: usize,
+}
+
+pub struct Cache {
+    connection: Connection,
+    path: PathBuf,
+    ttl: Duration,
+    time_entries_ttl: Duration,
+    refresh: bool,
+    allow_stale: bool,
+    /// Prefix of the keys, so the responses of different accounts are kept apart
+    scope: String,
+}
+
+impl Cache {
+    /// Opens the cache in the default cache dir. Values older than the TTL are not returned,
+    /// with `refresh` no values are returned at all but fetched values are still stored.
+    pub fn open_default(ttl: Duration, time_entries_ttl: Duration, refresh: bool) -> Result<Self> {
+        let dir = get_default_cache_dir();
+        create_dir_all(&dir).map_err(|e| CacheError(e.to_string()))?;
+        Cache::open(
+            &Path::new(&dir).join(CACHE_FILE_NAME),
+            ttl,
+            time_entries_ttl,
+            refresh,
+        )
+    }
+
+    pub fn open(
+        path: &Path,
+        ttl: Duration,
+        time_entries_ttl: Duration,
+        refresh: bool,
+    ) -> Result<Self> {
+        let connection = Connection::open(path).map_err(|e| CacheError(e.to_string()))?;
+        connection
+            .execute(
+                "CREATE TABLE IF NOT EXISTS responses (
+                    key TEXT PRIMARY KEY,
+                    value TEXT NOT NULL,
+                    fetched_at INTEGER NOT NULL
+                )",
+                params![],
+            )
+            .map_err(|e| CacheError(e.to_string()))?;
+
+        Ok(Cache {
+            connection,
+            path: path.to_owned(),
+            ttl,
+            time_entries_ttl,
+            refresh,
+            allow_stale: false,
+            scope: String::new(),
+        })
+    }
+
+    /// Returns values regardless of their age, used while the API is unreachable
+    pub fn allow_stale(mut self) -> Self {
+        self.allow_stale = true;
+        self
+    }
+
+    /// Keeps the responses of the account of the API key apart from other accounts. Without a
+    /// key only responses which aren't scoped to an account are read.
+    pub fn for_account(mut self, api_key: Option<&str>) -> Self {
+        self.scope = match api_key {
+            Some(k) => {
+                // The key itself is a credential, so only its hash ends up in the cache
+                let mut hasher = DefaultHasher::new();
+                k.hash(&mut hasher);
+                format!("{:016x}/", hasher.finish())
+            }
+            None => String::new(),
+        };
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the cached value if it's younger than the TTL of its key
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        if self.allow_stale {
+            return self.read(key, None);
+        }
+        if self.refresh {
+            return None;
+        }
+        self.read(key, Some(Utc::now() - self.max_age(key)))
+    }
+
+    /// Returns the TTL of the key
+    pub fn max_age(&self, key: &str) -> Duration {
+        if key.starts_with(KEY_TIME_ENTRIES) {
+            self.time_entries_ttl
+        } else if key == KEY_TRACKING {
+            Duration::seconds(TRACKING_TTL_SECS).min(self.ttl)
+        } else {
+            self.ttl
+        }
+    }
+
+    /// Returns the cached value regardless of its age, e.g. for shell completion which has to
+    /// work without any API call
+    pub fn get_stale<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.read(key, None)
+    }
+
+    /// Returns the cached value if it isn't older than the max age, regardless of the TTL
+    pub fn get_younger<T: DeserializeOwned>(&self, key: &str, max_age: Duration) -> Option<T> {
+        self.read(key, Some(Utc::now() - max_age))
+    }
+
+    fn read<T: DeserializeOwned>(&self, key: &str, since: Option<DateTime<Utc>>) -> Option<T> {
+        let result = self
+            .connection
+            .query_row(
+                "SELECT value FROM responses WHERE key = ?1 AND fetched_at >= ?2",
+                params![self.scoped(key), since.map_or(i64::MIN, |s| s.timestamp())],
+                |row| row.get::<_, String>(0),
+            )
+            .optional();
+
+        match result {
+            Ok(Some(v)) => serde_json::from_str(&v)
+                .map_err(|e| log::debug!("Unable to parse cached {}: {}", key, e))
+                .ok(),
+            Ok(None) => None,
+            Err(e) => {
+                log::debug!("Unable to read cached {}: {}", key, e);
+                None
+            }
+        }
+    }
+
+    /// Stores the value, failures are only logged as the cache is not relevant for the command
+    pub fn put<T: Serialize>(&self, key: &str, value: &T) {
+        let result = serde_json::to_string(value)
+            .map_err(|e| e.to_string())
+            .and_then(|v| {
+                self.connection
+                    .execute(
+                        "INSERT OR REPLACE INTO responses (key, value, fetched_at) VALUES (?1, ?2, ?3)",
+                        params![self.scoped(key), v, Utc::now().timestamp()],
+                    )
+                    .map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            log::debug!("Unable to cache {}: {}", key, e);
+        }
+
+        // Most ranges of time entries are only requested once, so expired ones are removed
+        if key.starts_with(KEY_TIME_ENTRIES) {
+            self.remove_expired(KEY_TIME_ENTRIES, Utc::now() - self.time_entries_ttl);
+        }
+    }
+
+    fn remove_expired(&self, prefix: &str, before: DateTime<Utc>) {
+        if let Err(e) = self.connection.execute(
+            "DELETE FROM responses WHERE substr(key, 1, length(?1)) = ?1 AND fetched_at < ?2",
+            params![self.scoped(prefix), before.timestamp()],
+        ) {
+            log::debug!("Unable to remove expired {}: {}", prefix, e);
+        }
+    }
+
+    /// Marks all values whose key starts with the prefix as outdated. They are kept for
+    /// [`Cache::get_stale`].
+    pub fn invalidate(&self, prefix: &str) {
+        if let Err(e) = self.connection.execute(
+            "UPDATE responses SET fetched_at = 0 WHERE substr(key, 1, length(?1)) = ?1",
+            params![self.scoped(prefix)],
+        ) {
+            log::debug!("Unable to invalidate cached {}: {}", prefix, e);
+        }
+    }
+
+    /// Removes all values of all accounts and returns how many were removed
+    pub fn clear(&self) -> Result<usize> {
+        self.connection
+            .execute("DELETE FROM responses", params![])
+            .map_err(|e| CacheError(e.to_string()))
+    }
+
+    /// Returns the values of the account, their keys are returned without the scope
+    pub fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT key, fetched_at, length(value) FROM responses
+                WHERE substr(key, 1, length(?1)) = ?1 ORDER BY key",
+            )
+            .map_err(|e| CacheError(e.to_string()))?;
+        let rows = statement
+            .query_map(params![self.scope], |row| {
+                let key: String = row.get(0)?;
+                Ok(CacheEntry {
+                    key: key[self.scope.len()..].to_owned(),
+                    fetched_at: DateTime::from_utc(
+                        NaiveDateTime::from_timestamp(row.get(1)?, 0),
+                        Utc,
+                    ),
+                    size: row.get::<_, i64>(2)? as usize,
+                })
+            })
+            .map_err(|e| CacheError(e.to_string()))?;
+
+        rows.collect::<rusqlite::Result<Vec<CacheEntry>>>()
+            .map_err(|e| CacheError(e.to_string()))
+    }
+
+    fn scoped(&self, key: &str) -> String {
+        format!("{}{}", self.scope, key)
+    }
+}
+
+/// Creates the key of the time entries in the given range
+pub fn time_entries_key(from: DateTime<Utc>, to: DateTime<Utc>) -> String {
+    format!(
+        "{}{}/{}",
+        KEY_TIME_ENTRIES,
+        from.timestamp(),
+        to.timestamp()
+    )
+}
+
+/// Widens the range to whole days in UTC, so ranges ending at "now" share a cached response
+pub fn whole_days(from: DateTime<Utc>, to: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = |t: DateTime<Utc>| DateTime::from_utc(t.date().naive_utc().and_hms(0, 0, 0), Utc);
+    let end = start(to);
+    let end = if end < to {
+        end + Duration::days(1)
+    } else {
+        end
+    };
+    (start(from), end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_cache(refresh: bool) -> Cache {
+        Cache::open(
+            Path::new(":memory:"),
+            Duration::hours(1),
+            Duration::zero(),
+            refresh,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_get_and_invalidate() {
+        let cache = open_cache(false);
+        cache.put(KEY_ACTIVITIES, &vec!["Development".to_owned()]);
+        cache.put(KEY_DEFAULT_SPACE, &"1".to_owned());
+        assert_eq!(
+            cache.get::<Vec<String>>(KEY_ACTIVITIES),
+            Some(vec!["Development".to_owned()])
+        );
+
+        cache.invalidate(KEY_ACTIVITIES);
+        assert_eq!(cache.get::<Vec<String>>(KEY_ACTIVITIES), None);
+        assert_eq!(
+            cache.get_stale::<Vec<String>>(KEY_ACTIVITIES),
+            Some(vec!["Development".to_owned()])
+        );
+        assert_eq!(cache.get::<String>(KEY_DEFAULT_SPACE), Some("1".to_owned()));
+
+        assert_eq!(cache.entries().unwrap().len(), 2);
+        assert_eq!(cache.clear().unwrap(), 2);
+        assert_eq!(cache.get_stale::<String>(KEY_DEFAULT_SPACE), None);
+    }
+
+    #[test]
+    fn test_for_account() {
+        let path = std::env::temp_dir().join(format!("tmlr-cache-{}", std::process::id()));
+        let open = |api_key| {
+            Cache::open(&path, Duration::hours(1), Duration::zero(), false)
+                .unwrap()
+                .for_account(api_key)
+        };
+
+        let cache = open(Some("first"));
+        cache.put(KEY_DEFAULT_SPACE, &"1".to_owned());
+        assert_eq!(cache.get::<String>(KEY_DEFAULT_SPACE), Some("1".to_owned()));
+        assert_eq!(cache.entries().unwrap()[0].key, KEY_DEFAULT_SPACE);
+
+        let other = open(Some("second"));
+        assert_eq!(other.get_stale::<String>(KEY_DEFAULT_SPACE), None);
+        assert!(other.entries().unwrap().is_empty());
+        other.put(KEY_DEFAULT_SPACE, &"2".to_owned());
+        other.invalidate(KEY_DEFAULT_SPACE);
+        assert_eq!(cache.get::<String>(KEY_DEFAULT_SPACE), Some("1".to_owned()));
+
+        assert_eq!(cache.clear().unwrap(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_whole_days() {
+        let at = |day, hour| {
+            DateTime::<Utc>::from_utc(
+                chrono::NaiveDate::from_ymd(2021, 6, day).and_hms(hour, 0, 0),
+                Utc,
+            )
+        };
+        assert_eq!(whole_days(at(1, 9), at(3, 17)), (at(1, 0), at(4, 0)));
+        assert_eq!(whole_days(at(1, 0), at(3, 0)), (at(1, 0), at(3, 0)));
+    }
+
+    #[test]
+    fn test_ttl_and_refresh() {
+        let cache = open_cache(false);
+        let key = time_entries_key(Utc::now() - Duration::days(1), Utc::now());
+        cache.put(&key, &Vec::<String>::new());
+        cache
+            .connection
+            .execute(
+                "UPDATE responses SET fetched_at = fetched_at - 1",
+                params![],
+            )
+            .unwrap();
+        assert_eq!(cache.get::<Vec<String>>(&key), None);
+        assert_eq!(
+            cache.get_younger::<Vec<String>>(&key, Duration::zero()),
+            None
+        );
+        assert_eq!(
+            cache.get_younger::<Vec<String>>(&key, Duration::minutes(1)),
+            Some(Vec::new())
+        );
+
+        let cache = open_cache(true);
+        cache.put(KEY_DEFAULT_SPACE, &"1".to_owned());
+        assert_eq!(cache.get::<String>(KEY_DEFAULT_SPACE), None);
+        assert_eq!(
+            cache.get_stale::<String>(KEY_DEFAULT_SPACE),
+            Some("1".to_owned())
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use super::{Cache, KEY_ACTIVITIES, KEY_TAGS_AND_MENTIONS};
+use crate::timeular::{ActivityListResponse, TagsAndMentionsResponse};
+use chrono::Duration;
+
+/// The names of activities, tags and mentions of the cached responses, used for shell
+/// completion which has to work without a network call
+#[derive(Debug, Clone, Default)]
+pub struct NameCache {
+    pub activities: Vec<String>,
+    pub tags: Vec<String>,
+    pub mentions: Vec<String>,
+}
+
+impl NameCache {
+    /// Loads the names regardless of the age of the cached responses, a missing or broken cache
+    /// results in empty names
+    pub fn load(api_key: Option<&str>) -> Self {
+        match Cache::open_default(Duration::zero(), Duration::zero(), false) {
+            Ok(cache) => NameCache::from_cache(&cache.for_account(api_key)),
+            Err(e) => {
+                log::debug!("Unable to open the cache for completion: {}", e);
+                NameCache::default()
+            }
+        }
+    }
+
+    fn from_cache(cache: &Cache) -> Self {
+        let mut names = NameCache::default();
+        if let Some(list) = cache.get_stale::<ActivityListResponse>(KEY_ACTIVITIES) {
+            names.activities = list
+                .activities
+                .into_iter()
+                .chain(list.inactive_activities)
+                .map(|a| a.name)
+                .collect();
+        }
+        if let Some(tnm) = cache.get_stale::<TagsAndMentionsResponse>(KEY_TAGS_AND_MENTIONS) {
+            names.tags = tnm.tags.into_iter().map(|t| t.label).collect();
+            names.mentions = tnm.mentions.into_iter().map(|t| t.label).collect();
+        }
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeular::TagResponse;
+    use std::path::Path;
+
+    #[test]
+    fn test_from_cache() {
+        let cache = Cache::open(
+            Path::new(":memory:"),
+            Duration::zero(),
+            Duration::zero(),
+            false,
+        )
+        .unwrap();
+        assert!(NameCache::from_cache(&cache).tags.is_empty());
+
+        let tag = |label: &str| TagResponse {
+            id: 1,
+            key: label.to_owned(),
+            label: label.to_owned(),
+            scope: String::new(),
+            space_id: String::new(),
+        };
+        cache.put(
+            KEY_TAGS_AND_MENTIONS,
+            &TagsAndMentionsResponse {
+                tags: vec![tag("bug")],
+                mentions: vec![tag("acme")],
+            },
+        );
+        cache.invalidate(KEY_TAGS_AND_MENTIONS);
+
+        let names = NameCache::from_cache(&cache);
+        assert_eq!(names.tags, vec!["bug"]);
+        assert_eq!(names.mentions, vec!["acme"]);
+        assert!(names.activities.is_empty());
+    }
+}
@@ -0,0 +1,120 @@
+use super::{resolver::Resolver, ARG_ACTIVITY, CMD_ACTIVITY};
+use crate::{error::Error::InvalidCommandError, settings::Settings, timeular::Timeular, Result};
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+pub const CMD_ARCHIVE: &str = "archive";
+pub const CMD_UNARCHIVE: &str = "unarchive";
+pub const CMD_ASSIGN: &str = "assign";
+pub const CMD_UNASSIGN: &str = "unassign";
+
+const ARG_SIDE: &str = "side";
+const DEVICE_SIDES: u8 = 8;
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_ACTIVITY)
+        .alias("ac")
+        .about("Manages activities")
+        .subcommand(
+            SubCommand::with_name(CMD_ARCHIVE)
+                .about("Archives an activity, its time entries are kept")
+                .arg(activity_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name(CMD_UNARCHIVE)
+                .about("Restores an archived activity")
+                .arg(activity_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name(CMD_ASSIGN)
+                .about("Assigns an activity to a side of the tracker")
+                .arg(activity_arg())
+                .arg(
+                    Arg::with_name(ARG_SIDE)
+                        .help("Defines the side of the tracker (1-8)")
+                        .long(ARG_SIDE)
+                        .short("s")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(|v| parse_side(&v).map(|_| ())),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name(CMD_UNASSIGN)
+                .about("Removes the activity from its side of the tracker")
+                .arg(activity_arg()),
+        )
+}
+
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let resolver = Resolver::new(tmlr, cfg);
+    let activity_input = |m: &ArgMatches| {
+        m.value_of(ARG_ACTIVITY)
+            .expect("An activity was provided")
+            .to_owned()
+    };
+
+    match matches.subcommand() {
+        (CMD_ARCHIVE, Some(sub_matches)) => {
+            let activity = resolver.activity(&activity_input(sub_matches))?;
+            tmlr.archive_activity(&activity.id)?;
+            log::info!("Activity \"{}\" was archived.", activity.name);
+            Ok(())
+        }
+        (CMD_UNARCHIVE, Some(sub_matches)) => {
+            let activity = resolver.archived_activity(&activity_input(sub_matches))?;
+            tmlr.unarchive_activity(&activity.id)?;
+            log::info!("Activity \"{}\" was unarchived.", activity.name);
+            Ok(())
+        }
+        (CMD_ASSIGN, Some(sub_matches)) => {
+            let activity = resolver.activity(&activity_input(sub_matches))?;
+            let side = sub_matches
+                .value_of(ARG_SIDE)
+                .and_then(|v| parse_side(v).ok())
+                .expect("A valid side was provided");
+            tmlr.assign_device_side(&activity.id, side)?;
+            log::info!(
+                "Activity \"{}\" was assigned to side {}.",
+                activity.name,
+                side
+            );
+            Ok(())
+        }
+        (CMD_UNASSIGN, Some(sub_matches)) => {
+            let activity = resolver.activity(&activity_input(sub_matches))?;
+            match activity.device_side {
+                Some(side) => {
+                    tmlr.unassign_device_side(&activity.id, side)?;
+                    log::info!(
+                        "Activity \"{}\" was removed from side {}.",
+                        activity.name,
+                        side
+                    );
+                }
+                None => log::info!("Activity \"{}\" is not assigned to a side.", activity.name),
+            }
+            Ok(())
+        }
+        _ => {
+            println!("{}", matches.usage());
+            Err(InvalidCommandError)
+        }
+    }
+}
+
+fn activity_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name(ARG_ACTIVITY)
+        .help("Defines the activity id, name or alias")
+        .required(true)
+}
+
+fn parse_side(value: &str) -> std::result::Result<u8, String> {
+    match value.parse::<u8>() {
+        Ok(v) if (1..=DEVICE_SIDES).contains(&v) => Ok(v),
+        _ => Err(format!("The side has to be between 1 and {}", DEVICE_SIDES)),
+    }
+}
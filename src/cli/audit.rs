@@ -0,0 +1,455 @@
+use super::{
+    balance::WorkTargets,
+    picker::{confirm, pick_activity},
+    resolver::Resolver,
+    time_entry::{check_time_range, format_range, CMD_SPLIT},
+    ARG_FROM, ARG_TO, CMD_TIME_ENTRY,
+};
+use crate::{
+    error::Error::InvalidTimeError,
+    settings::Settings,
+    timeular::{TimeEntryResponse, Timeular},
+    util::{
+        color::ansi_foreground,
+        time::{format_duration, local_to_utc, parse_duration, parse_time},
+    },
+    Result,
+};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, Utc, Weekday};
+use clap::{App, Arg, ArgMatches};
+
+pub const CMD_AUDIT: &str = "audit";
+
+const ARG_NO_FIX: &str = "no-fix";
+
+const DEFAULT_FROM: &str = "-7d";
+const DEFAULT_MAX_DURATION: &str = "10h";
+const DEFAULT_MIN_GAP: &str = "5m";
+const WORKING_HOURS_SEPARATOR: char = '-';
+
+/// A finding of the audit, entries are borrowed from the fetched time entries
+#[derive(Debug)]
+enum Issue<'e> {
+    Gap(DateTime<Utc>, DateTime<Utc>),
+    Overlap(&'e TimeEntryResponse, &'e TimeEntryResponse),
+    Long(&'e TimeEntryResponse),
+    ZeroLength(&'e TimeEntryResponse),
+}
+
+impl Issue<'_> {
+    fn started_at(&self) -> DateTime<Utc> {
+        match self {
+            Issue::Gap(from, _) => *from,
+            Issue::Overlap(_, e) | Issue::Long(e) | Issue::ZeroLength(e) => e.duration.started_at,
+        }
+    }
+}
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_AUDIT)
+        .about("Reports untracked gaps in the working hours, overlapping, long and empty time entries and offers fixes")
+        .arg(
+            Arg::with_name(ARG_FROM)
+                .help("Defines the start of the audit, defaults to \"-7d\"")
+                .long(ARG_FROM)
+                .short("f")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name(ARG_TO)
+                .help("Defines the end of the audit, defaults to now")
+                .long(ARG_TO)
+                .short("t")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name(ARG_NO_FIX)
+                .help("Only reports the issues without asking for fixes")
+                .long(ARG_NO_FIX),
+        )
+}
+
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let settings = cfg.and_then(|c| c.audit.clone()).unwrap_or_default();
+    let now = Local::now();
+    let from = parse_time(matches.value_of(ARG_FROM).unwrap_or(DEFAULT_FROM), now)?;
+    let to = parse_time(matches.value_of(ARG_TO).unwrap_or("now"), now)?;
+    check_time_range(from, to)?;
+    let max_duration = parse_duration(
+        settings
+            .max_duration
+            .as_deref()
+            .unwrap_or(DEFAULT_MAX_DURATION),
+    )?;
+    let min_gap = parse_duration(settings.min_gap.as_deref().unwrap_or(DEFAULT_MIN_GAP))?;
+
+    let windows = match &settings.working_hours {
+        Some(v) => {
+            let work = match cfg.and_then(|c| c.targets.as_ref()) {
+                Some(t) => Some(WorkTargets::from_settings(t)?),
+                None => None,
+            };
+            working_windows(
+                parse_working_hours(v)?,
+                from,
+                to.min(now.with_timezone(&Utc)),
+                |day| match &work {
+                    Some(w) => w.expected(day) > Duration::zero(),
+                    None => day.weekday() != Weekday::Sat && day.weekday() != Weekday::Sun,
+                },
+            )
+        }
+        None => {
+            log::info!("Gaps are not reported, please set the working hours in the [audit] section of the settings.");
+            Vec::new()
+        }
+    };
+
+    let mut entries = tmlr.list_time_entries(from, to)?;
+    entries.sort_by_key(|e| e.duration.started_at);
+    let issues = find_issues(&entries, &windows, max_duration, min_gap);
+    if issues.is_empty() {
+        log::info!("No issues found between {}.", format_range(from, to));
+        return Ok(());
+    }
+
+    let resolver = Resolver::new(tmlr, cfg);
+    for issue in issues.iter() {
+        println!("{}", describe(&resolver, issue));
+    }
+    if matches.is_present(ARG_NO_FIX) {
+        return Ok(());
+    }
+
+    for issue in issues.iter() {
+        fix(tmlr, cfg, issue, max_duration)?;
+    }
+    Ok(())
+}
+
+/// Asks for a fix of the issue and applies it. Zero-length entries have no fix.
+fn fix(
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+    issue: &Issue,
+    max_duration: Duration,
+) -> Result<()> {
+    match issue {
+        Issue::Gap(from, to) => {
+            if !confirm(&format!(
+                "Fill the gap {} with an activity?",
+                format_range(*from, *to)
+            ))? {
+                return Ok(());
+            }
+            if let Some(activity) = pick_activity(tmlr, cfg)? {
+                let created =
+                    tmlr.create_time_entry(&activity.id, *from, *to, Default::default())?;
+                log::info!("Time entry {} was created.", created.id);
+            }
+        }
+        Issue::Overlap(first, second) => {
+            // An earlier fix may have changed the entries already
+            let first = tmlr.get_time_entry(&first.id)?;
+            let second = tmlr.get_time_entry(&second.id)?;
+            match overlap_fix(&first, &second) {
+                OverlapFix::Trim(to) => {
+                    if !confirm(&format!(
+                        "Trim time entry {} to end at {}?",
+                        first.id,
+                        to.with_timezone(&Local).format("%H:%M")
+                    ))? {
+                        return Ok(());
+                    }
+                    let note = first.note.clone().unwrap_or_default();
+                    tmlr.edit_time_entry(
+                        &first.id,
+                        &first.activity_id,
+                        first.duration.started_at,
+                        to,
+                        note,
+                    )?;
+                    log::info!("Time entry {} was trimmed.", first.id);
+                }
+                OverlapFix::Split => log::info!(
+                    "Time entry {} lies within time entry {}, split it with \"tmlr {} {} {} --at {}\".",
+                    second.id,
+                    first.id,
+                    CMD_TIME_ENTRY,
+                    CMD_SPLIT,
+                    first.id,
+                    second
+                        .duration
+                        .started_at
+                        .with_timezone(&Local)
+                        .format("%H:%M")
+                ),
+                OverlapFix::None => {}
+            }
+        }
+        Issue::Long(entry) => {
+            let entry = tmlr.get_time_entry(&entry.id)?;
+            if entry.duration.stopped_at - entry.duration.started_at <= max_duration
+                || !confirm(&format!(
+                    "Cap time entry {} to {}?",
+                    entry.id,
+                    format_duration(max_duration)
+                ))?
+            {
+                return Ok(());
+            }
+            let from = entry.duration.started_at;
+            let note = entry.note.clone().unwrap_or_default();
+            tmlr.edit_time_entry(
+                &entry.id,
+                &entry.activity_id,
+                from,
+                from + max_duration,
+                note,
+            )?;
+            log::info!("Time entry {} was capped.", entry.id);
+        }
+        Issue::ZeroLength(_) => {}
+    }
+    Ok(())
+}
+
+/// The fix of two overlapping entries, the first one starts earlier
+#[derive(Debug, PartialEq)]
+enum OverlapFix {
+    /// The first entry ends at the start of the second one
+    Trim(DateTime<Utc>),
+    /// The second entry lies within the first one, trimming would drop the rest of the first one
+    Split,
+    /// The entries don't overlap (anymore)
+    None,
+}
+
+fn overlap_fix(first: &TimeEntryResponse, second: &TimeEntryResponse) -> OverlapFix {
+    let (first, second) = (&first.duration, &second.duration);
+    if second.started_at >= first.stopped_at || second.stopped_at <= first.started_at {
+        OverlapFix::None
+    } else if second.started_at <= first.started_at {
+        OverlapFix::Split
+    } else if second.stopped_at >= first.stopped_at {
+        OverlapFix::Trim(second.started_at)
+    } else {
+        OverlapFix::Split
+    }
+}
+
+fn describe(resolver: &Resolver, issue: &Issue) -> String {
+    let entry = |e: &TimeEntryResponse| {
+        let (color, name) = match resolver.activity(&e.activity_id) {
+            Ok(a) => (a.color, a.name),
+            Err(_) => (String::new(), e.activity_id.to_owned()),
+        };
+        format!(
+            "{} {} {} ({})",
+            e.id,
+            ansi_foreground(&color, "●"),
+            name,
+            format_range(e.duration.started_at, e.duration.stopped_at)
+        )
+    };
+
+    match issue {
+        Issue::Gap(from, to) => format!(
+            "Gap        {} ({})",
+            format_range(*from, *to),
+            format_duration(*to - *from)
+        ),
+        Issue::Overlap(first, second) => {
+            format!("Overlap    {} and {}", entry(first), entry(second))
+        }
+        Issue::Long(e) => format!(
+            "Long       {} {}",
+            entry(e),
+            format_duration(e.duration.stopped_at - e.duration.started_at)
+        ),
+        Issue::ZeroLength(e) => format!("Empty      {}", entry(e)),
+    }
+}
+
+/// Parses working hours like `09:00-17:00`
+fn parse_working_hours(input: &str) -> Result<(NaiveTime, NaiveTime)> {
+    let parse = |v: &str| NaiveTime::parse_from_str(v.trim(), "%H:%M").ok();
+    match input.split_once(WORKING_HOURS_SEPARATOR) {
+        Some((start, end)) => match (parse(start), parse(end)) {
+            (Some(s), Some(e)) if s < e => Ok((s, e)),
+            _ => Err(InvalidTimeError(input.to_owned())),
+        },
+        None => Err(InvalidTimeError(input.to_owned())),
+    }
+}
+
+/// Returns the working hours of all working days, limited to the audited range
+fn working_windows(
+    (start, end): (NaiveTime, NaiveTime),
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    is_working_day: impl Fn(chrono::NaiveDate) -> bool,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let first = from.with_timezone(&Local).date().naive_local();
+    let last = to.with_timezone(&Local).date().naive_local();
+    first
+        .iter_days()
+        .take_while(|d| *d <= last)
+        .filter(|d| is_working_day(*d))
+        .filter_map(|d| {
+            let window_start = local_to_utc(d.and_time(start))?.max(from);
+            let window_end = local_to_utc(d.and_time(end))?.min(to);
+            Some((window_start, window_end)).filter(|(s, e)| s < e)
+        })
+        .collect()
+}
+
+/// Finds all issues, the entries have to be sorted by their start
+fn find_issues<'e>(
+    entries: &'e [TimeEntryResponse],
+    windows: &[(DateTime<Utc>, DateTime<Utc>)],
+    max_duration: Duration,
+    min_gap: Duration,
+) -> Vec<Issue<'e>> {
+    let mut issues = Vec::new();
+
+    let mut latest: Option<&TimeEntryResponse> = None;
+    for e in entries {
+        let duration = e.duration.stopped_at - e.duration.started_at;
+        if duration <= Duration::zero() {
+            issues.push(Issue::ZeroLength(e));
+            continue;
+        }
+        if duration > max_duration {
+            issues.push(Issue::Long(e));
+        }
+        match latest {
+            Some(l) if e.duration.started_at < l.duration.stopped_at => {
+                issues.push(Issue::Overlap(l, e));
+                if e.duration.stopped_at > l.duration.stopped_at {
+                    latest = Some(e);
+                }
+            }
+            _ => latest = Some(e),
+        }
+    }
+
+    for (start, end) in windows {
+        let mut cursor = *start;
+        for e in entries
+            .iter()
+            .filter(|e| e.duration.started_at < e.duration.stopped_at)
+            .filter(|e| e.duration.started_at < *end && e.duration.stopped_at > *start)
+        {
+            if e.duration.started_at - cursor >= min_gap {
+                issues.push(Issue::Gap(cursor, e.duration.started_at));
+            }
+            cursor = cursor.max(e.duration.stopped_at);
+        }
+        if *end - cursor >= min_gap {
+            issues.push(Issue::Gap(cursor, *end));
+        }
+    }
+
+    issues.sort_by_key(|i| i.started_at());
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, min: u32) -> DateTime<Utc> {
+        Utc.ymd(2021, 6, 1).and_hms(hour, min, 0)
+    }
+
+    #[test]
+    fn test_find_issues() {
+        let entries = vec![
+            TimeEntryResponse::new("1", "1", at(1, 0), at(12, 0)),
+            TimeEntryResponse::new("2", "1", at(11, 0), at(12, 3)),
+            TimeEntryResponse::new("3", "1", at(13, 0), at(13, 0)),
+            TimeEntryResponse::new("4", "1", at(14, 0), at(16, 0)),
+        ];
+        let windows = [(at(9, 0), at(17, 0))];
+        let issues = find_issues(
+            &entries,
+            &windows,
+            Duration::hours(10),
+            Duration::minutes(5),
+        );
+        let summary: Vec<String> = issues
+            .iter()
+            .map(|i| match i {
+                Issue::Gap(from, to) => {
+                    format!("gap {}-{}", from.format("%H:%M"), to.format("%H:%M"))
+                }
+                Issue::Overlap(a, b) => format!("overlap {} {}", a.id, b.id),
+                Issue::Long(e) => format!("long {}", e.id),
+                Issue::ZeroLength(e) => format!("empty {}", e.id),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                "long 1",
+                "overlap 1 2",
+                "gap 12:03-14:00",
+                "empty 3",
+                "gap 16:00-17:00"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overlap_fix() {
+        let first = TimeEntryResponse::new("1", "1", at(9, 0), at(12, 0));
+        assert_eq!(
+            overlap_fix(
+                &first,
+                &TimeEntryResponse::new("2", "1", at(11, 0), at(13, 0))
+            ),
+            OverlapFix::Trim(at(11, 0))
+        );
+        assert_eq!(
+            overlap_fix(
+                &first,
+                &TimeEntryResponse::new("2", "1", at(11, 0), at(12, 0))
+            ),
+            OverlapFix::Trim(at(11, 0))
+        );
+        assert_eq!(
+            overlap_fix(
+                &first,
+                &TimeEntryResponse::new("2", "1", at(10, 0), at(11, 0))
+            ),
+            OverlapFix::Split
+        );
+        assert_eq!(
+            overlap_fix(
+                &first,
+                &TimeEntryResponse::new("2", "1", at(12, 0), at(13, 0))
+            ),
+            OverlapFix::None
+        );
+    }
+
+    #[test]
+    fn test_parse_working_hours() {
+        assert_eq!(
+            parse_working_hours("09:00 - 17:30").unwrap(),
+            (NaiveTime::from_hms(9, 0, 0), NaiveTime::from_hms(17, 30, 0))
+        );
+        assert!(parse_working_hours("17:00-09:00").is_err());
+        assert!(parse_working_hours("9 to 5").is_err());
+    }
+}
@@ -0,0 +1,329 @@
+use super::{note::create_note, resolver::Resolver};
+use crate::{
+    settings::{AutotrackRule, Defaults, Settings},
+    timeular::Timeular,
+    util::{
+        git::remote_urls,
+        time::{format_duration, parse_duration},
+    },
+    Result,
+};
+use chrono::{DateTime, Duration, Utc};
+use clap::{App, Arg, ArgMatches};
+use glob::Pattern;
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+    thread,
+};
+
+pub const CMD_AUTOTRACK: &str = "autotrack";
+
+const ARG_ONCE: &str = "once";
+
+const DEFAULT_INTERVAL: &str = "30s";
+const DEFAULT_MIN_DWELL: &str = "2m";
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_AUTOTRACK)
+        .about("Switches the tracking to the activity of the first matching rule in [[autotrack.rules]]")
+        .arg(
+            Arg::with_name(ARG_ONCE)
+                .help("Only prints the matching rule instead of watching")
+                .long(ARG_ONCE),
+        )
+}
+
+/// The environment the rules are evaluated against
+#[derive(Debug, Default)]
+struct Context {
+    cwd: PathBuf,
+    remotes: Vec<String>,
+    window: Option<String>,
+}
+
+/// Delays changes of the matching rule until they lasted for the minimum dwell time, so
+/// switching windows quickly doesn't switch the tracking
+#[derive(Debug, Default)]
+struct Dwell {
+    current: Option<usize>,
+    candidate: Option<(Option<usize>, DateTime<Utc>)>,
+}
+
+impl Dwell {
+    /// Returns the matching rule once it differs from the current one for the minimum dwell time
+    fn update(
+        &mut self,
+        matched: Option<usize>,
+        now: DateTime<Utc>,
+        min_dwell: Duration,
+    ) -> Option<Option<usize>> {
+        if matched == self.current {
+            self.candidate = None;
+            return None;
+        }
+
+        match self.candidate {
+            Some((candidate, since)) if candidate == matched => {
+                if now - since < min_dwell {
+                    return None;
+                }
+                self.current = matched;
+                self.candidate = None;
+                Some(matched)
+            }
+            _ => {
+                self.candidate = Some((matched, now));
+                None
+            }
+        }
+    }
+}
+
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let settings = cfg.and_then(|c| c.autotrack.clone()).unwrap_or_default();
+    let rules = settings.rules.clone().unwrap_or_default();
+    if rules.is_empty() {
+        log::info!("No rules found, add them as [[autotrack.rules]] to the settings.");
+        return Ok(());
+    }
+
+    let home = home::home_dir().unwrap_or_default();
+    if matches.is_present(ARG_ONCE) {
+        let context = read_context(
+            settings.cwd_command.as_deref(),
+            settings.window_command.as_deref(),
+        );
+        match find_rule(&rules, &context, &home) {
+            Some(idx) => println!(
+                "Rule {} matches, activity \"{}\"",
+                idx + 1,
+                rules[idx].activity
+            ),
+            None => println!("No rule matches."),
+        }
+        return Ok(());
+    }
+
+    let interval = parse_duration(settings.interval.as_deref().unwrap_or(DEFAULT_INTERVAL))?;
+    let min_dwell = parse_duration(settings.min_dwell.as_deref().unwrap_or(DEFAULT_MIN_DWELL))?;
+    log::info!(
+        "Evaluating {} rules every {}, changes have to last {}.",
+        rules.len(),
+        format_duration(interval),
+        format_duration(min_dwell)
+    );
+
+    let mut dwell = Dwell::default();
+    loop {
+        let context = read_context(
+            settings.cwd_command.as_deref(),
+            settings.window_command.as_deref(),
+        );
+        let matched = find_rule(&rules, &context, &home);
+        match dwell.update(matched, Utc::now(), min_dwell) {
+            Some(Some(idx)) => {
+                if let Err(e) = switch_to_rule(tmlr, cfg, &rules[idx]) {
+                    log::error!("{}", e);
+                }
+            }
+            // The running tracking is kept, it may have been started by hand
+            Some(None) => log::info!("No rule matches anymore."),
+            None => {}
+        }
+        thread::sleep(interval.to_std().unwrap_or_default());
+    }
+}
+
+fn switch_to_rule(tmlr: &Timeular, cfg: Option<&Settings>, rule: &AutotrackRule) -> Result<()> {
+    let resolver = Resolver::new(tmlr, cfg);
+    let activity = resolver.activity(&rule.activity)?;
+    if let Some(tracking) = tmlr.current_tracking()? {
+        if tracking.activity_id == activity.id {
+            return Ok(());
+        }
+    }
+
+    let defaults = Defaults {
+        tags: rule.tags.clone(),
+        ..Defaults::default()
+    };
+    let note = create_note(&resolver, rule.note.clone(), &defaults, false)?;
+    tmlr.switch_tracking(&activity.id, Some(note))?;
+    log::info!("Tracking \"{}\".", activity.name);
+    Ok(())
+}
+
+/// Collects the environment, commands which fail are logged and ignored
+fn read_context(cwd_command: Option<&str>, window_command: Option<&str>) -> Context {
+    let cwd = cwd_command
+        .and_then(run)
+        .map(PathBuf::from)
+        .or_else(|| env::current_dir().ok())
+        .unwrap_or_default();
+    let remotes = remote_urls(&cwd).unwrap_or_default();
+
+    Context {
+        cwd,
+        remotes,
+        window: window_command.and_then(run),
+    }
+}
+
+fn run(command: &str) -> Option<String> {
+    match Command::new("sh").arg("-c").arg(command).output() {
+        Ok(output) if output.status.success() => {
+            Some(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+        }
+        Ok(output) => {
+            log::debug!(
+                "{} failed: {}",
+                command,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            None
+        }
+        Err(e) => {
+            log::debug!("{} failed: {}", command, e);
+            None
+        }
+    }
+}
+
+/// Returns the index of the first rule whose conditions all match, rules without conditions
+/// never match
+fn find_rule(rules: &[AutotrackRule], context: &Context, home: &Path) -> Option<usize> {
+    let matches_cwd = |pattern: &str| {
+        let pattern = expand_home(pattern, home);
+        context
+            .cwd
+            .ancestors()
+            .any(|dir| matches_glob(&pattern, &dir.to_string_lossy()))
+    };
+    let matches_remote = |pattern: &str| context.remotes.iter().any(|r| matches_glob(pattern, r));
+    let matches_window =
+        |pattern: &str| matches!(&context.window, Some(w) if matches_glob(pattern, w));
+
+    rules.iter().position(|rule| {
+        (rule.cwd.is_some() || rule.remote.is_some() || rule.window.is_some())
+            && condition(&rule.cwd, matches_cwd)
+            && condition(&rule.remote, matches_remote)
+            && condition(&rule.window, matches_window)
+    })
+}
+
+/// A condition which is not given always matches
+fn condition(pattern: &Option<String>, matches: impl Fn(&str) -> bool) -> bool {
+    match pattern {
+        Some(p) => matches(p),
+        None => true,
+    }
+}
+
+fn expand_home(pattern: &str, home: &Path) -> String {
+    match pattern.strip_prefix('~') {
+        Some(rest) => format!("{}{}", home.display(), rest),
+        None => pattern.to_owned(),
+    }
+}
+
+fn matches_glob(pattern: &str, value: &str) -> bool {
+    match Pattern::new(pattern) {
+        Ok(p) => p.matches(value),
+        Err(e) => {
+            log::debug!("Invalid pattern {}: {}", pattern, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(activity: &str) -> AutotrackRule {
+        AutotrackRule {
+            activity: activity.to_owned(),
+            ..AutotrackRule::default()
+        }
+    }
+
+    #[test]
+    fn test_find_rule() {
+        let rules = vec![
+            rule("none"),
+            AutotrackRule {
+                window: Some("*Slack*".to_owned()),
+                ..rule("chat")
+            },
+            AutotrackRule {
+                cwd: Some("~/work/acme".to_owned()),
+                remote: Some("*github.com?acme/*".to_owned()),
+                ..rule("acme")
+            },
+            AutotrackRule {
+                cwd: Some("~/work/*".to_owned()),
+                ..rule("work")
+            },
+        ];
+        let home = Path::new("/home/user");
+        let context = |cwd: &str, remote: &str, window: &str| Context {
+            cwd: PathBuf::from(cwd),
+            remotes: vec![remote.to_owned()],
+            window: Some(window.to_owned()),
+        };
+
+        let find = |c: Context| find_rule(&rules, &c, home).map(|idx| rules[idx].activity.as_str());
+        assert_eq!(
+            find(context(
+                "/home/user/work/acme/src",
+                "git@github.com:acme/app.git",
+                "Slack | acme"
+            )),
+            Some("chat")
+        );
+        assert_eq!(
+            find(context(
+                "/home/user/work/acme/src",
+                "git@github.com:acme/app.git",
+                "vim"
+            )),
+            Some("acme")
+        );
+        assert_eq!(
+            find(context(
+                "/home/user/work/acme",
+                "git@gitlab.com:other/app.git",
+                "vim"
+            )),
+            Some("work")
+        );
+        assert_eq!(find(context("/tmp", "", "vim")), None);
+    }
+
+    #[test]
+    fn test_dwell() {
+        let start = Utc::now();
+        let at = |secs| start + Duration::seconds(secs);
+        let min_dwell = Duration::minutes(2);
+        let mut dwell = Dwell::default();
+
+        assert_eq!(dwell.update(Some(1), at(0), min_dwell), None);
+        assert_eq!(dwell.update(Some(1), at(60), min_dwell), None);
+        assert_eq!(dwell.update(Some(1), at(120), min_dwell), Some(Some(1)));
+        assert_eq!(dwell.update(Some(1), at(150), min_dwell), None);
+
+        // A short visit of another window doesn't switch
+        assert_eq!(dwell.update(Some(2), at(180), min_dwell), None);
+        assert_eq!(dwell.update(Some(1), at(210), min_dwell), None);
+        assert_eq!(dwell.update(Some(2), at(240), min_dwell), None);
+        assert_eq!(dwell.update(Some(2), at(330), min_dwell), None);
+        assert_eq!(dwell.update(None, at(360), min_dwell), None);
+        assert_eq!(dwell.update(None, at(480), min_dwell), Some(None));
+    }
+}
@@ -0,0 +1,251 @@
+use super::timesheet::{format_hours, split_by_day};
+use crate::{
+    error::Error::{CalendarFileError, InvalidTimeRangeError},
+    settings::{Settings, Targets},
+    timeular::Timeular,
+    util::{
+        calendar::{parse_date, parse_days, parse_ics},
+        time::{parse_duration, start_of_day},
+    },
+    Result,
+};
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use clap::{App, Arg, ArgMatches};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+};
+
+pub const CMD_BALANCE: &str = "balance";
+
+const ARG_SINCE: &str = "since";
+
+/// Expected working time per day, holidays and vacation days are free
+pub struct WorkTargets {
+    hours: [Duration; 7],
+    days_off: HashSet<NaiveDate>,
+}
+
+/// Expected and tracked time of a week starting on Monday
+#[derive(Debug, PartialEq)]
+struct WeekBalance {
+    start: NaiveDate,
+    expected: Duration,
+    tracked: Duration,
+}
+
+impl WorkTargets {
+    pub fn from_settings(targets: &Targets) -> Result<WorkTargets> {
+        let weekdays = [
+            &targets.mon,
+            &targets.tue,
+            &targets.wed,
+            &targets.thu,
+            &targets.fri,
+            &targets.sat,
+            &targets.sun,
+        ];
+        let mut hours = [Duration::zero(); 7];
+        for (idx, value) in weekdays.iter().enumerate() {
+            if let Some(v) = value {
+                hours[idx] = parse_duration(v)?;
+            }
+        }
+
+        let mut days_off: HashSet<NaiveDate> = HashSet::new();
+        days_off.extend(parse_days(targets.holidays.as_deref().unwrap_or_default())?);
+        days_off.extend(parse_days(targets.vacation.as_deref().unwrap_or_default())?);
+        if let Some(path) = &targets.holidays_file {
+            let content = fs::read_to_string(path)
+                .map_err(|e| CalendarFileError(path.to_owned(), e.to_string()))?;
+            days_off.extend(parse_ics(&content));
+        }
+
+        Ok(WorkTargets { hours, days_off })
+    }
+
+    pub fn expected(&self, day: NaiveDate) -> Duration {
+        if self.days_off.contains(&day) {
+            return Duration::zero();
+        }
+        self.hours[day.weekday().num_days_from_monday() as usize]
+    }
+}
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_BALANCE)
+        .about("Compares the tracked with the expected working time of the targets settings, today is included")
+        .arg(
+            Arg::with_name(ARG_SINCE)
+                .help("Defines the first day, e.g. \"2021-06-01\". Defaults to the targets setting or the start of the year.")
+                .long(ARG_SINCE)
+                .short("s")
+                .takes_value(true)
+                .required(false),
+        )
+}
+
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let targets = match cfg.and_then(|c| c.targets.as_ref()) {
+        Some(v) => v,
+        None => {
+            log::info!(
+                "No working-hours targets found, please add a [targets] section to the settings."
+            );
+            return Ok(());
+        }
+    };
+    let work = WorkTargets::from_settings(targets)?;
+
+    let today = Local::today().naive_local();
+    let since = match matches.value_of(ARG_SINCE).or(targets.since.as_deref()) {
+        Some(v) => parse_date(v)?,
+        None => NaiveDate::from_ymd(today.year(), 1, 1),
+    };
+    if since > today {
+        return Err(InvalidTimeRangeError(since.to_string(), today.to_string()));
+    }
+
+    let from = start_of_day(since);
+    let to = start_of_day(today.succ());
+    let mut tracked: HashMap<NaiveDate, Duration> = HashMap::new();
+    for entry in tmlr.list_time_entries(from, to)? {
+        for (day, duration) in split_by_day(&entry) {
+            let sum = tracked.entry(day).or_insert_with(Duration::zero);
+            *sum = *sum + duration;
+        }
+    }
+
+    let weeks = weekly_balance(&work, &tracked, since, today);
+    println!(
+        "{:<10} {:>9} {:>9} {:>9} {:>9}",
+        "Week", "Expected", "Tracked", "Balance", "Running"
+    );
+    let mut running = Duration::zero();
+    for week in weeks.iter() {
+        running = running + week.tracked - week.expected;
+        println!(
+            "{:<10} {:>9} {:>9} {:>9} {:>9}",
+            week.start.format("%Y-%m-%d").to_string(),
+            format_hours(week.expected),
+            format_hours(week.tracked),
+            format_balance(week.tracked - week.expected),
+            format_balance(running)
+        );
+    }
+
+    let expected = weeks
+        .iter()
+        .fold(Duration::zero(), |acc, w| acc + w.expected);
+    let tracked = weeks
+        .iter()
+        .fold(Duration::zero(), |acc, w| acc + w.tracked);
+    println!(
+        "{:<10} {:>9} {:>9} {:>9}",
+        "Total",
+        format_hours(expected),
+        format_hours(tracked),
+        format_balance(tracked - expected)
+    );
+    Ok(())
+}
+
+/// Sums up the expected and tracked time per week between both days (inclusive), the first
+/// week starts at `since` even if it's not a Monday
+fn weekly_balance(
+    work: &WorkTargets,
+    tracked: &HashMap<NaiveDate, Duration>,
+    since: NaiveDate,
+    until: NaiveDate,
+) -> Vec<WeekBalance> {
+    let mut weeks: Vec<WeekBalance> = Vec::new();
+    for day in since.iter_days().take_while(|d| *d <= until) {
+        if weeks.is_empty() || day.weekday().num_days_from_monday() == 0 {
+            weeks.push(WeekBalance {
+                start: day,
+                expected: Duration::zero(),
+                tracked: Duration::zero(),
+            });
+        }
+        let week = weeks.last_mut().expect("A week was added");
+        week.expected = week.expected + work.expected(day);
+        week.tracked = week.tracked + tracked.get(&day).copied().unwrap_or_else(Duration::zero);
+    }
+    weeks
+}
+
+/// Formats the difference with a sign, e.g. `+1:30` or `-0:45`
+fn format_balance(duration: Duration) -> String {
+    let minutes = duration.num_minutes();
+    let sign = if minutes < 0 { '-' } else { '+' };
+    format!("{}{}:{:02}", sign, minutes.abs() / 60, minutes.abs() % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(2021, 6, day)
+    }
+
+    #[test]
+    fn test_work_targets() {
+        let targets = Targets {
+            mon: Some("8h".to_owned()),
+            fri: Some("4h30m".to_owned()),
+            holidays: Some(vec!["2021-06-04".to_owned()]),
+            vacation: Some(vec!["2021-06-14..2021-06-18".to_owned()]),
+            ..Targets::default()
+        };
+        let work = WorkTargets::from_settings(&targets).unwrap();
+        assert_eq!(work.expected(date(7)), Duration::hours(8));
+        assert_eq!(work.expected(date(8)), Duration::zero());
+        assert_eq!(work.expected(date(11)), Duration::minutes(270));
+        assert_eq!(work.expected(date(4)), Duration::zero());
+        assert_eq!(work.expected(date(14)), Duration::zero());
+
+        let invalid = Targets {
+            mon: Some("eight".to_owned()),
+            ..Targets::default()
+        };
+        assert!(WorkTargets::from_settings(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_weekly_balance() {
+        let targets = Targets {
+            mon: Some("8h".to_owned()),
+            tue: Some("8h".to_owned()),
+            ..Targets::default()
+        };
+        let work = WorkTargets::from_settings(&targets).unwrap();
+        let mut tracked = HashMap::new();
+        tracked.insert(date(1), Duration::hours(9));
+        tracked.insert(date(7), Duration::hours(6));
+
+        // 2021-06-01 is a Tuesday, the first week is shortened
+        let weeks = weekly_balance(&work, &tracked, date(1), date(8));
+        assert_eq!(
+            weeks,
+            vec![
+                WeekBalance {
+                    start: date(1),
+                    expected: Duration::hours(8),
+                    tracked: Duration::hours(9),
+                },
+                WeekBalance {
+                    start: date(7),
+                    expected: Duration::hours(16),
+                    tracked: Duration::hours(6),
+                }
+            ]
+        );
+        assert_eq!(format_balance(Duration::minutes(-45)), "-0:45");
+        assert_eq!(format_balance(Duration::minutes(90)), "+1:30");
+    }
+}
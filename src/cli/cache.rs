@@ -0,0 +1,86 @@
+use crate::{
+    cache::Cache,
+    error::Error::InvalidCommandError,
+    settings::Settings,
+    util::time::{format_duration, parse_duration},
+    Result,
+};
+use chrono::Utc;
+use clap::{App, ArgMatches, SubCommand};
+use std::fs;
+
+pub const CMD_CACHE: &str = "cache";
+pub const CMD_CLEAR: &str = "clear";
+pub const CMD_STATUS: &str = "status";
+pub const ARG_REFRESH: &str = "refresh";
+
+const DEFAULT_TTL: &str = "1h";
+const DEFAULT_TIME_ENTRIES_TTL: &str = "5m";
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_CACHE)
+        .about("Manages the local cache of activities, tags, mentions, spaces and time entries")
+        .subcommand(SubCommand::with_name(CMD_CLEAR).about("Removes all cached responses"))
+        .subcommand(
+            SubCommand::with_name(CMD_STATUS).about("Shows the cached responses and their age"),
+        )
+}
+
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    cfg: Option<&Settings>,
+    api_key: Option<&str>,
+) -> Result<()> {
+    let cache = open_cache(cfg, api_key, false)?;
+    match matches.subcommand() {
+        (CMD_CLEAR, Some(_)) => {
+            let removed = cache.clear()?;
+            log::info!("{} cached responses were removed.", removed);
+            Ok(())
+        }
+        (CMD_STATUS, Some(_)) => {
+            let size = fs::metadata(cache.path()).map(|m| m.len()).unwrap_or(0);
+            println!("{} ({} KiB)", cache.path().display(), size / 1024);
+            if !is_enabled(cfg) {
+                println!("The cache is disabled in the settings.");
+            }
+
+            let now = Utc::now();
+            for entry in cache.entries()? {
+                let age = now - entry.fetched_at;
+                let state = if entry.fetched_at.timestamp() == 0 {
+                    "invalidated".to_owned()
+                } else if age > cache.max_age(&entry.key) {
+                    format!("expired, fetched {} ago", format_duration(age))
+                } else {
+                    format!("fetched {} ago", format_duration(age))
+                };
+                println!("{:<40} {:>8} B  {}", entry.key, entry.size, state);
+            }
+            Ok(())
+        }
+        _ => {
+            println!("{}", matches.usage());
+            Err(InvalidCommandError)
+        }
+    }
+}
+
+/// Opens the cache with the TTLs of the settings, `refresh` skips all cached responses
+pub fn open_cache(cfg: Option<&Settings>, api_key: Option<&str>, refresh: bool) -> Result<Cache> {
+    let settings = cfg.and_then(|c| c.cache.clone()).unwrap_or_default();
+    let ttl = parse_duration(settings.ttl.as_deref().unwrap_or(DEFAULT_TTL))?;
+    let time_entries_ttl = parse_duration(
+        settings
+            .time_entries_ttl
+            .as_deref()
+            .unwrap_or(DEFAULT_TIME_ENTRIES_TTL),
+    )?;
+    Cache::open_default(ttl, time_entries_ttl, refresh).map(|c| c.for_account(api_key))
+}
+
+pub fn is_enabled(cfg: Option<&Settings>) -> bool {
+    cfg.and_then(|c| c.cache.as_ref())
+        .and_then(|c| c.enabled)
+        .unwrap_or(true)
+}
@@ -0,0 +1,397 @@
+use super::{CMD_ACTIVITY, CMD_MENTION, CMD_TAG};
+use crate::{
+    cache::names::NameCache, error::Error::InvalidCommandError, settings::Settings, Result,
+};
+use clap::{App, AppSettings, Arg, ArgMatches, Shell, SubCommand};
+use std::{collections::HashMap, iter::once};
+
+pub const CMD_COMPLETIONS: &str = "completions";
+pub const CMD_COMPLETE: &str = "__complete";
+
+const ARG_SHELL: &str = "shell";
+const ARG_KIND: &str = "kind";
+
+pub const SHELL_BASH: &str = "bash";
+pub const SHELL_ZSH: &str = "zsh";
+pub const SHELL_FISH: &str = "fish";
+const SHELL_POWERSHELL: &str = "powershell";
+
+const BIN_NAME: &str = "tmlr";
+
+/// Arguments named like one of these kinds are completed with `tmlr __complete <kind>`
+const KINDS: [&str; 3] = [CMD_ACTIVITY, CMD_TAG, CMD_MENTION];
+
+/// An argument which is completed with `tmlr __complete <kind>`
+#[derive(Debug, PartialEq)]
+struct DynamicArg {
+    /// The subcommands leading to the argument, aliases get their own entries
+    path: Vec<String>,
+    kind: &'static str,
+    /// The flags of an option, e.g. `--activity` and `-a`, empty for positional arguments
+    flags: Vec<String>,
+}
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(CMD_COMPLETIONS)
+        .about("Prints a completion script for the given shell, e.g. `tmlr completions bash > /etc/bash_completion.d/tmlr`")
+        .arg(
+            Arg::with_name(ARG_SHELL)
+                .help("Defines the shell")
+                .possible_values(&[SHELL_BASH, SHELL_ZSH, SHELL_FISH, SHELL_POWERSHELL])
+                .required(true),
+        )
+}
+
+pub fn create_complete_command<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(CMD_COMPLETE)
+        .setting(AppSettings::Hidden)
+        .about("Prints aliases and cached names for shell completion")
+        .arg(
+            Arg::with_name(ARG_KIND)
+                .possible_values(&[CMD_ACTIVITY, CMD_TAG, CMD_MENTION])
+                .required(false),
+        )
+}
+
+pub fn handle_match<'a>(matches: &ArgMatches<'a>, mut app: App) -> Result<()> {
+    let shell = matches.value_of(ARG_SHELL).unwrap_or_default();
+    let args = dynamic_args(&app);
+    let script = match shell {
+        SHELL_BASH => with_bash_dynamic_args(generate(&mut app, Shell::Bash), &args),
+        SHELL_ZSH => with_zsh_dynamic_args(generate(&mut app, Shell::Zsh), &args),
+        SHELL_FISH => with_fish_dynamic_args(generate(&mut app, Shell::Fish), &args),
+        SHELL_POWERSHELL => generate(&mut app, Shell::PowerShell),
+        _ => {
+            println!("{}", matches.usage());
+            return Err(InvalidCommandError);
+        }
+    };
+
+    print!("{}", script);
+    Ok(())
+}
+
+pub fn handle_complete<'a>(
+    matches: &ArgMatches<'a>,
+    cfg: Option<&Settings>,
+    api_key: Option<&str>,
+) -> Result<()> {
+    let names = NameCache::load(api_key);
+    for v in completion_values(matches.value_of(ARG_KIND), cfg, &names) {
+        println!("{}", v);
+    }
+    Ok(())
+}
+
+fn generate(app: &mut App, shell: Shell) -> String {
+    let mut buf = Vec::new();
+    app.gen_completions_to(BIN_NAME, shell, &mut buf);
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+/// Collects the arguments named like a completion kind from the app, so new commands are
+/// completed without maintaining a list
+fn dynamic_args(app: &App) -> Vec<DynamicArg> {
+    let mut args = Vec::new();
+    collect_dynamic_args(app, &mut Vec::new(), &mut args);
+    args
+}
+
+fn collect_dynamic_args(app: &App, path: &mut Vec<String>, args: &mut Vec<DynamicArg>) {
+    let kind = |name: &str| KINDS.iter().find(|k| **k == name).copied();
+    // clap 2 has no accessors for the arguments, its own script generators read `App::p` as well
+    for pos in app.p.positionals.values() {
+        if let Some(kind) = kind(pos.b.name) {
+            args.push(DynamicArg {
+                path: path.clone(),
+                kind,
+                flags: Vec::new(),
+            });
+        }
+    }
+    for opt in app.p.opts.iter() {
+        if let Some(kind) = kind(opt.b.name) {
+            args.push(DynamicArg {
+                path: path.clone(),
+                kind,
+                flags: opt
+                    .s
+                    .long
+                    .map(|l| format!("--{}", l))
+                    .into_iter()
+                    .chain(opt.s.short.map(|s| format!("-{}", s)))
+                    .collect(),
+            });
+        }
+    }
+
+    for sub in app.p.subcommands.iter() {
+        let aliases = sub
+            .p
+            .meta
+            .aliases
+            .iter()
+            .flatten()
+            .map(|(a, _)| a.to_string());
+        for name in once(sub.p.meta.name.clone()).chain(aliases) {
+            path.push(name);
+            collect_dynamic_args(sub, path, args);
+            path.pop();
+        }
+    }
+}
+
+/// Collects the aliases of the settings first, followed by the cached names. Values are only
+/// printed once.
+fn completion_values(kind: Option<&str>, cfg: Option<&Settings>, names: &NameCache) -> Vec<String> {
+    let alias = cfg.and_then(|c| c.alias.as_ref());
+    let sorted_keys = |map: Option<&HashMap<String, String>>| {
+        let mut keys: Vec<String> = map.map(|m| m.keys().cloned().collect()).unwrap_or_default();
+        keys.sort();
+        keys
+    };
+
+    let mut values = Vec::new();
+    if matches!(kind, None | Some(CMD_ACTIVITY)) {
+        values.extend(sorted_keys(alias.and_then(|a| a.activity.as_ref())));
+        values.extend(names.activities.iter().cloned());
+    }
+    if matches!(kind, None | Some(CMD_TAG)) {
+        values.extend(sorted_keys(alias.and_then(|a| a.tag.as_ref())));
+        values.extend(names.tags.iter().cloned());
+    }
+    if matches!(kind, None | Some(CMD_MENTION)) {
+        values.extend(sorted_keys(alias.and_then(|a| a.mention.as_ref())));
+        values.extend(names.mentions.iter().cloned());
+    }
+
+    let mut seen = Vec::new();
+    values.retain(|v| {
+        if seen.contains(v) {
+            false
+        } else {
+            seen.push(v.clone());
+            true
+        }
+    });
+    values
+}
+
+/// Options are matched by the flag before the current word, positional arguments by the exact
+/// subcommand path
+fn with_bash_dynamic_args(script: String, args: &[DynamicArg]) -> String {
+    let (options, positionals): (Vec<&DynamicArg>, Vec<&DynamicArg>) =
+        args.iter().partition(|a| !a.flags.is_empty());
+    let cases: Vec<String> = options
+        .iter()
+        .map(|a| {
+            let patterns: Vec<String> = a
+                .flags
+                .iter()
+                .flat_map(|f| {
+                    let path = a.path.join(" ");
+                    vec![
+                        format!("\"{} {}\"", path, f),
+                        format!("\"{} \"*\" {}\"", path, f),
+                    ]
+                })
+                .collect();
+            format!("        {}) kind=\"{}\" ;;", patterns.join("|"), a.kind)
+        })
+        .chain(
+            positionals
+                .iter()
+                .map(|a| format!("        \"{}\") kind=\"{}\" ;;", a.path.join(" "), a.kind)),
+        )
+        .collect();
+
+    format!(
+        r#"{script}
+_{bin}_dynamic() {{
+    _{bin} "$@"
+    local kind
+    case "${{COMP_WORDS[*]:1:COMP_CWORD-1}}" in
+{cases}
+        *) return 0 ;;
+    esac
+    local IFS=$'\n'
+    COMPREPLY+=( $(compgen -W "$({bin} {complete} ${{kind}} 2>/dev/null)" -- "${{COMP_WORDS[COMP_CWORD]}}") )
+}}
+
+complete -F _{bin}_dynamic -o bashdefault -o default {bin}
+"#,
+        script = script,
+        bin = BIN_NAME,
+        cases = cases.join("\n"),
+        complete = CMD_COMPLETE
+    )
+}
+
+/// Replaces the file completion which clap uses for positional arguments named like a
+/// completion kind, e.g. `activity`, and adds the completion to the values of options
+fn with_zsh_dynamic_args(script: String, args: &[DynamicArg]) -> String {
+    let kinds = KINDS;
+    let mut functions = String::new();
+    for kind in kinds.iter() {
+        functions.push_str(&format!(
+            "\n_{bin}_complete_{kind}() {{\n    local -a values\n    values=(\"${{(@f)$({bin} {complete} {kind} 2>/dev/null)}}\")\n    compadd -a values\n}}\n",
+            bin = BIN_NAME,
+            kind = kind,
+            complete = CMD_COMPLETE
+        ));
+    }
+
+    // Options are written as `'-a+[help]' \` followed by `'--activity=[help]' \`
+    let option_kind = |line: &str| {
+        args.iter().find_map(|a| {
+            a.flags
+                .iter()
+                .filter(|f| f.starts_with("--"))
+                .find(|f| line.starts_with(&format!("'{}=[", f)))
+                .map(|_| a.kind)
+        })
+    };
+    let help = |line: &str| line.find('[').map(|idx| line[idx..].to_owned());
+    let complete_option = |line: &str, kind: &str| {
+        line.replace(
+            "]' \\",
+            &format!("]:{}:_{}_complete_{}' \\", kind, BIN_NAME, kind),
+        )
+    };
+
+    let source: Vec<&str> = script.lines().collect();
+    let lines: Vec<String> = source
+        .iter()
+        .enumerate()
+        .map(|(idx, &line)| {
+            let trimmed = line.trim_start();
+            if let Some(kind) = option_kind(trimmed) {
+                return complete_option(line, kind);
+            }
+            // The short flag of an option is on the line before its long flag
+            if let Some(next) = source.get(idx + 1).map(|l| l.trim_start()) {
+                if let Some(kind) = option_kind(next) {
+                    if trimmed.starts_with("'-") && help(trimmed) == help(next) {
+                        return complete_option(line, kind);
+                    }
+                }
+            }
+            match kinds.iter().find(|k| {
+                trimmed.starts_with(&format!("'::{} -- ", k))
+                    || trimmed.starts_with(&format!("':{} -- ", k))
+            }) {
+                Some(kind) if line.ends_with(":_files' \\") => line.replace(
+                    ":_files' \\",
+                    &format!(":_{}_complete_{}' \\", BIN_NAME, kind),
+                ),
+                _ => line.to_owned(),
+            }
+        })
+        .collect();
+
+    // The generated script calls the completion function at its end, so the helper functions
+    // have to be defined before
+    format!(
+        "#compdef {}\n{}\n{}\n",
+        BIN_NAME,
+        functions,
+        lines
+            .join("\n")
+            .trim_start_matches(&format!("#compdef {}", BIN_NAME))
+    )
+}
+
+fn with_fish_dynamic_args(script: String, args: &[DynamicArg]) -> String {
+    let lines: Vec<String> = args
+        .iter()
+        .map(|a| {
+            let condition = a
+                .path
+                .iter()
+                .map(|p| format!("__fish_seen_subcommand_from {}", p))
+                .collect::<Vec<String>>()
+                .join("; and ");
+            let flags: String = a
+                .flags
+                .iter()
+                .map(|f| match f.strip_prefix("--") {
+                    Some(long) => format!(" -l {}", long),
+                    None => format!(" -s {}", f.trim_start_matches('-')),
+                })
+                .collect();
+            format!(
+                "complete -c {bin} -n \"{condition}\"{flags} -f -a \"({bin} {complete} {kind} 2>/dev/null)\"",
+                bin = BIN_NAME,
+                condition = condition,
+                flags = flags,
+                complete = CMD_COMPLETE,
+                kind = a.kind
+            )
+        })
+        .collect();
+
+    format!("{}{}\n", script, lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Alias;
+
+    #[test]
+    fn test_completion_values() {
+        let mut alias = Alias {
+            activity: None,
+            tag: None,
+            mention: None,
+        };
+        alias.add_activity_alias("dev".to_owned(), "1".to_owned());
+        alias.add_activity_alias("admin".to_owned(), "2".to_owned());
+        alias.add_tag_alias("bug".to_owned(), "3".to_owned());
+        let cfg = Settings {
+            alias: Some(alias),
+            ..Settings::default()
+        };
+        let names = NameCache {
+            activities: vec!["Development".to_owned(), "dev".to_owned()],
+            tags: vec!["bug".to_owned(), "feature".to_owned()],
+            mentions: vec!["acme".to_owned()],
+        };
+
+        assert_eq!(
+            completion_values(Some(CMD_ACTIVITY), Some(&cfg), &names),
+            vec!["admin", "dev", "Development"]
+        );
+        assert_eq!(
+            completion_values(Some(CMD_TAG), Some(&cfg), &names),
+            vec!["bug", "feature"]
+        );
+        assert_eq!(
+            completion_values(Some(CMD_MENTION), None, &names),
+            vec!["acme"]
+        );
+        assert_eq!(completion_values(None, None, &names).len(), 5);
+    }
+
+    #[test]
+    fn test_dynamic_args() {
+        let args = dynamic_args(&super::super::create_app());
+        let find = |path: &[&str]| {
+            args.iter()
+                .find(|a| a.path == path)
+                .map(|a| (a.kind, a.flags.join(" ")))
+        };
+
+        assert_eq!(find(&["switch"]), Some((CMD_ACTIVITY, String::new())));
+        assert_eq!(find(&["pomodoro"]), Some((CMD_ACTIVITY, String::new())));
+        assert_eq!(find(&["edit", "tag"]), Some((CMD_TAG, String::new())));
+        assert_eq!(
+            find(&["te", "split"]),
+            Some((CMD_ACTIVITY, "--activity -a".to_owned()))
+        );
+        assert_eq!(find(&["continue"]), None);
+
+        let script = with_bash_dynamic_args(String::new(), &args);
+        assert!(script.contains(r#""te split --activity"|"te split "*" --activity""#));
+    }
+}
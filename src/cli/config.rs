@@ -1,10 +1,12 @@
 use crate::{
-    cli::{ARG_ALIAS, CMD_ACTIVITY, CMD_TAG},
-    error::Error::InvalidCommandError,
+    cli::{resolver::Resolver, ARG_ALIAS, CMD_ACTIVITY, CMD_MENTION, CMD_TAG},
+    error::Error::{AuthenticationInformationMissingError, InvalidCommandError},
     settings::{error::ConfigurationError::FileNotFoundError, Authentication, Settings},
+    timeular::{Timeular, TimeularAuth},
     Result,
 };
 use clap::{App, Arg, ArgMatches, SubCommand};
+use std::collections::{HashMap, HashSet};
 
 pub const CMD_SET: &str = "set";
 const CMD_DELETE: &str = "delete";
@@ -18,6 +20,15 @@ pub const CMD_ALIAS: &str = "alias";
 pub const ARG_ID: &str = "id";
 
 const CMD_AUTH: &str = "auth";
+const CMD_VERIFY: &str = "verify";
+const ARG_NO_VERIFY: &str = "no-verify";
+const ARG_PRUNE: &str = "prune";
+
+#[derive(Debug, PartialEq)]
+enum AliasState {
+    Dangling,
+    Archived,
+}
 
 pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
     App::new(CMD_CONFIG)
@@ -47,13 +58,18 @@ pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
                                 .about("Creates an alias for an activity")
                                 .arg(
                                     Arg::with_name(ARG_ID)
-                                        .help("Sets the activity id")
+                                        .help("Sets the activity (id, name or alias)")
                                         .required(true),
                                 )
                                 .arg(
                                     Arg::with_name(ARG_ALIAS)
                                         .help("Sets the alias for the given activity id")
                                         .required(true),
+                                )
+                                .arg(
+                                    Arg::with_name(ARG_NO_VERIFY)
+                                        .help("Skips checking that the activity exists")
+                                        .long(ARG_NO_VERIFY),
                                 ),
                         )
                         .subcommand(
@@ -61,12 +77,36 @@ pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
                                 .about("Creates an alias for a tag")
                                 .arg(
                                     Arg::with_name(ARG_ID)
-                                        .help("Sets the tag id")
+                                        .help("Sets the tag (id, label or alias)")
                                         .required(true),
                                 )
                                 .arg(
                                     Arg::with_name(ARG_ALIAS)
                                         .help("Sets the alias for the given tag id"),
+                                )
+                                .arg(
+                                    Arg::with_name(ARG_NO_VERIFY)
+                                        .help("Skips checking that the tag exists")
+                                        .long(ARG_NO_VERIFY),
+                                ),
+                        )
+                        .subcommand(
+                            SubCommand::with_name(CMD_MENTION)
+                                .about("Creates an alias for a mention")
+                                .arg(
+                                    Arg::with_name(ARG_ID)
+                                        .help("Sets the mention (id, label or alias)")
+                                        .required(true),
+                                )
+                                .arg(
+                                    Arg::with_name(ARG_ALIAS)
+                                        .help("Sets the alias for the given mention id")
+                                        .required(true),
+                                )
+                                .arg(
+                                    Arg::with_name(ARG_NO_VERIFY)
+                                        .help("Skips checking that the mention exists")
+                                        .long(ARG_NO_VERIFY),
                                 ),
                         ),
                 ),
@@ -93,16 +133,39 @@ pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
                                     Arg::with_name(ARG_ALIAS)
                                         .help("Deletes an alias with the defined alias of a tag").required(true),
                                 ),
+                        )
+                        .subcommand(
+                            SubCommand::with_name(CMD_MENTION)
+                                .about("Deletes an alias of a mention")
+                                .arg(
+                                    Arg::with_name(ARG_ALIAS)
+                                        .help("Deletes an alias with the defined alias of a mention")
+                                        .required(true),
+                                ),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name(CMD_ALIAS)
+                .about("Maintains aliases")
+                .subcommand(
+                    SubCommand::with_name(CMD_VERIFY)
+                        .about("Checks that all aliases point to existing and not archived activities, tags and mentions")
+                        .arg(
+                            Arg::with_name(ARG_PRUNE)
+                                .help("Removes dangling and archived aliases")
+                                .long(ARG_PRUNE),
                         ),
                 ),
         )
 }
 
-pub fn handle_match<'a>(matches: &ArgMatches<'a>) -> Result<()> {
+pub fn handle_match<'a>(matches: &ArgMatches<'a>, auth: Option<TimeularAuth>) -> Result<()> {
     if let (sub_cmd, Some(sub_matches)) = matches.subcommand() {
         match sub_cmd {
-            CMD_SET => handle_match_set(sub_matches),
+            CMD_SET => handle_match_set(sub_matches, auth),
             CMD_DELETE => handle_match_delete(sub_matches),
+            CMD_ALIAS => handle_match_alias(sub_matches, auth),
             _ => {
                 println!("{}", matches.usage());
                 Err(InvalidCommandError)
@@ -114,7 +177,7 @@ pub fn handle_match<'a>(matches: &ArgMatches<'a>) -> Result<()> {
     }
 }
 
-fn handle_match_set<'a>(matches: &ArgMatches<'a>) -> Result<()> {
+fn handle_match_set<'a>(matches: &ArgMatches<'a>, auth: Option<TimeularAuth>) -> Result<()> {
     if let (sub_cmd, Some(sub_matches)) = matches.subcommand() {
         match sub_cmd {
             CMD_AUTH => set_auth_to_config(
@@ -126,7 +189,7 @@ fn handle_match_set<'a>(matches: &ArgMatches<'a>) -> Result<()> {
                     .expect("A required argument api_secret"),
                 sub_matches.value_of(ARG_CONFIG),
             ),
-            CMD_ALIAS => handle_match_set_alias(sub_matches),
+            CMD_ALIAS => handle_match_set_alias(sub_matches, auth),
             _ => Err(InvalidCommandError),
         }
     } else {
@@ -145,27 +208,26 @@ fn set_auth_to_config(api_key: &str, api_secret: &str, custom_path: Option<&str>
     cfg.save(custom_path).map_err(|e| e.into())
 }
 
-fn handle_match_set_alias<'a>(matches: &ArgMatches<'a>) -> Result<()> {
+fn handle_match_set_alias<'a>(matches: &ArgMatches<'a>, auth: Option<TimeularAuth>) -> Result<()> {
     if let (sub_cmd, Some(sub_matches)) = matches.subcommand() {
+        let alias = sub_matches
+            .value_of(ARG_ALIAS)
+            .expect("A required argument alias");
+        let input = sub_matches
+            .value_of(ARG_ID)
+            .expect("A required argument id");
+        let custom_path = sub_matches.value_of(ARG_CONFIG);
+
+        let id = if sub_matches.is_present(ARG_NO_VERIFY) {
+            input.to_owned()
+        } else {
+            resolve_alias_target(&create_timeular(auth)?, custom_path, sub_cmd, input)?
+        };
+
         match sub_cmd {
-            CMD_ACTIVITY => add_activity_alias(
-                sub_matches
-                    .value_of(ARG_ALIAS)
-                    .expect("A required argument alias"),
-                sub_matches
-                    .value_of(ARG_ID)
-                    .expect("A required argument id"),
-                sub_matches.value_of(ARG_CONFIG),
-            ),
-            CMD_TAG => add_tag_alias(
-                sub_matches
-                    .value_of(ARG_ALIAS)
-                    .expect("A required argument alias"),
-                sub_matches
-                    .value_of(ARG_ID)
-                    .expect("A required argument id"),
-                sub_matches.value_of(ARG_CONFIG),
-            ),
+            CMD_ACTIVITY => add_activity_alias(alias, &id, custom_path),
+            CMD_TAG => add_tag_alias(alias, &id, custom_path),
+            CMD_MENTION => add_mention_alias(alias, &id, custom_path),
             _ => Err(InvalidCommandError),
         }
     } else {
@@ -179,6 +241,12 @@ pub fn add_tag_alias(alias: &str, tag_id: &str, custom_path: Option<&str>) -> Re
     cfg.save(custom_path).map_err(|e| e.into())
 }
 
+fn add_mention_alias(alias: &str, mention_id: &str, custom_path: Option<&str>) -> Result<()> {
+    let mut cfg = load_settings(custom_path)?;
+    cfg.add_mention_alias(alias.to_string(), mention_id.to_string());
+    cfg.save(custom_path).map_err(|e| e.into())
+}
+
 pub fn add_activity_alias(alias: &str, activity_id: &str, custom_path: Option<&str>) -> Result<()> {
     let mut cfg = load_settings(custom_path)?;
     cfg.add_activity_alias(alias.to_string(), activity_id.to_string());
@@ -191,8 +259,147 @@ pub fn add_activity_alias(alias: &str, activity_id: &str, custom_path: Option<&s
     }
 }
 
+fn create_timeular<'a>(auth: Option<TimeularAuth>) -> Result<Timeular<'a>> {
+    Timeular::new(auth.ok_or(AuthenticationInformationMissingError)?)
+}
+
+/// Resolves the target of a new alias to the id of an existing and not archived activity, tag or
+/// mention
+fn resolve_alias_target(
+    tmlr: &Timeular,
+    custom_path: Option<&str>,
+    sub_cmd: &str,
+    input: &str,
+) -> Result<String> {
+    let cfg = load_settings(custom_path)?;
+    let resolver = Resolver::new(tmlr, Some(&cfg));
+    match sub_cmd {
+        CMD_ACTIVITY => resolver.activity(input).map(|a| a.id),
+        CMD_TAG => resolver.tag(input).map(|t| t.id.to_string()),
+        CMD_MENTION => resolver.mention(input).map(|m| m.id.to_string()),
+        _ => Err(InvalidCommandError),
+    }
+}
+
+fn handle_match_alias<'a>(matches: &ArgMatches<'a>, auth: Option<TimeularAuth>) -> Result<()> {
+    match matches.subcommand() {
+        (CMD_VERIFY, Some(sub_matches)) => verify_aliases(
+            &create_timeular(auth)?,
+            sub_matches.is_present(ARG_PRUNE),
+            sub_matches.value_of(ARG_CONFIG),
+        ),
+        _ => {
+            println!("{}", matches.usage());
+            Err(InvalidCommandError)
+        }
+    }
+}
+
+fn verify_aliases(tmlr: &Timeular, prune: bool, custom_path: Option<&str>) -> Result<()> {
+    let mut cfg = load_settings(custom_path)?;
+    let alias = cfg.alias.clone();
+
+    let activities = alias
+        .as_ref()
+        .and_then(|a| a.activity.clone())
+        .unwrap_or_default();
+    let tags = alias
+        .as_ref()
+        .and_then(|a| a.tag.clone())
+        .unwrap_or_default();
+    let mentions = alias.and_then(|a| a.mention).unwrap_or_default();
+
+    let mut invalid = Vec::new();
+    if !activities.is_empty() {
+        let list = tmlr.list_activities()?;
+        let existing = list
+            .activities
+            .iter()
+            .chain(list.inactive_activities.iter())
+            .map(|a| a.id.to_owned())
+            .collect();
+        let archived = list
+            .archived_activities
+            .iter()
+            .map(|a| a.id.to_owned())
+            .collect();
+        for (a, id, state) in check_aliases(&activities, &existing, &archived) {
+            invalid.push((CMD_ACTIVITY, a, id, state));
+        }
+    }
+    if !tags.is_empty() || !mentions.is_empty() {
+        let tnm = tmlr.list_tags_and_mentions()?;
+        let existing_tags = tnm.tags.iter().map(|t| t.id.to_string()).collect();
+        for (a, id, state) in check_aliases(&tags, &existing_tags, &HashSet::new()) {
+            invalid.push((CMD_TAG, a, id, state));
+        }
+        let existing_mentions = tnm.mentions.iter().map(|m| m.id.to_string()).collect();
+        for (a, id, state) in check_aliases(&mentions, &existing_mentions, &HashSet::new()) {
+            invalid.push((CMD_MENTION, a, id, state));
+        }
+    }
+
+    if invalid.is_empty() {
+        log::info!("All aliases are valid.");
+        return Ok(());
+    }
+
+    for (kind, a, id, state) in invalid.iter() {
+        match state {
+            AliasState::Dangling => log::info!(
+                "Alias \"{}\" points to the {} \"{}\" which doesn't exist.",
+                a,
+                kind,
+                id
+            ),
+            AliasState::Archived => log::info!(
+                "Alias \"{}\" points to the archived {} \"{}\".",
+                a,
+                kind,
+                id
+            ),
+        }
+    }
+
+    if prune {
+        for (kind, a, _, _) in invalid.iter() {
+            match *kind {
+                CMD_ACTIVITY => cfg.remove_activity_alias(a),
+                CMD_TAG => cfg.remove_tag_alias(a),
+                _ => cfg.remove_mention_alias(a),
+            }
+        }
+        cfg.save(custom_path)?;
+        log::info!("{} alias(es) removed.", invalid.len());
+    }
+
+    Ok(())
+}
+
+/// Returns all aliases (sorted by alias) which point to a missing or archived id
+fn check_aliases(
+    aliases: &HashMap<String, String>,
+    existing: &HashSet<String>,
+    archived: &HashSet<String>,
+) -> Vec<(String, String, AliasState)> {
+    let mut result: Vec<(String, String, AliasState)> = aliases
+        .iter()
+        .filter_map(|(a, id)| {
+            if archived.contains(id) {
+                Some((a.to_owned(), id.to_owned(), AliasState::Archived))
+            } else if !existing.contains(id) {
+                Some((a.to_owned(), id.to_owned(), AliasState::Dangling))
+            } else {
+                None
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| a.0.cmp(&b.0));
+    result
+}
+
 fn load_settings(custom_path: Option<&str>) -> Result<Settings> {
-    let settings = Settings::new(custom_path);
+    let settings = Settings::new_global(custom_path);
     if custom_path.is_none() {
         match settings {
             Ok(v) => Ok(v),
@@ -232,6 +439,12 @@ fn handle_match_delete_alias<'a>(matches: &ArgMatches<'a>) -> Result<()> {
                     .expect("A required argument alias"),
                 sub_matches.value_of(ARG_CONFIG),
             ),
+            CMD_MENTION => delete_mention_alias(
+                sub_matches
+                    .value_of(ARG_ALIAS)
+                    .expect("A required argument alias"),
+                sub_matches.value_of(ARG_CONFIG),
+            ),
             _ => Err(InvalidCommandError),
         }
     } else {
@@ -245,8 +458,39 @@ fn delete_tag_alias(alias: &str, custom_path: Option<&str>) -> Result<()> {
     cfg.save(custom_path).map_err(|e| e.into())
 }
 
+fn delete_mention_alias(alias: &str, custom_path: Option<&str>) -> Result<()> {
+    let mut cfg = load_settings(custom_path)?;
+    cfg.remove_mention_alias(alias);
+    cfg.save(custom_path).map_err(|e| e.into())
+}
+
 fn delete_activity_alias(alias: &str, custom_path: Option<&str>) -> Result<()> {
     let mut cfg = load_settings(custom_path)?;
     cfg.remove_activity_alias(alias);
     cfg.save(custom_path).map_err(|e| e.into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_aliases() {
+        let mut aliases = HashMap::with_capacity(3);
+        aliases.insert("dev".to_owned(), "1".to_owned());
+        aliases.insert("old".to_owned(), "2".to_owned());
+        aliases.insert("gone".to_owned(), "3".to_owned());
+
+        let existing = vec!["1".to_owned()].into_iter().collect();
+        let archived = vec!["2".to_owned()].into_iter().collect();
+
+        assert_eq!(
+            check_aliases(&aliases, &existing, &archived),
+            vec![
+                ("gone".to_owned(), "3".to_owned(), AliasState::Dangling),
+                ("old".to_owned(), "2".to_owned(), AliasState::Archived),
+            ]
+        );
+        assert!(check_aliases(&HashMap::new(), &existing, &archived).is_empty());
+    }
+}
@@ -1,8 +1,25 @@
 use super::{
     config::{add_activity_alias, add_tag_alias},
-    ARG_ALIAS, ARG_CONFIG, ARG_SPACE_ID, CMD_ACTIVITY, CMD_MENTION, CMD_TAG, CMD_TIME_ENTRY,
+    note::{create_note, create_tags_arg},
+    picker::pick_activity,
+    resolver::Resolver,
+    sync::record_offline,
+    time_entry::check_time_range,
+    ARG_ACTIVITY, ARG_ALIAS, ARG_CONFIG, ARG_CREATE_TAGS, ARG_FROM, ARG_NOTE, ARG_SPACE_ID, ARG_TO,
+    CMD_ACTIVITY, CMD_MENTION, CMD_TAG, CMD_TIME_ENTRY,
 };
-use crate::{error::Error::InvalidCommandError, timeular::Timeular, Result};
+use crate::{
+    error::Error::InvalidCommandError,
+    journal::Operation,
+    settings::Settings,
+    timeular::Timeular,
+    util::{
+        color::parse_color,
+        time::{format_duration, parse_time},
+    },
+    Result,
+};
+use chrono::Local;
 use clap::{App, Arg, ArgMatches, SubCommand};
 
 pub const CMD_CREATE: &str = "create";
@@ -33,7 +50,7 @@ pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
                 )
                 .arg(
                     Arg::with_name(ARG_ACTIVITY_COLOR)
-                        .help("Defines the color the activity should have in the UI clients. If no color will be provided a random one will be generated.")
+                        .help("Defines the color the activity should have in the UI clients as hex value (e.g. #1a2b3c), CSS color name or \"random\". If no color will be provided a random one will be generated.")
                         .long(ARG_ACTIVITY_COLOR)
                         .short("c")
                         .takes_value(true)
@@ -85,19 +102,52 @@ pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
         .subcommand(
             SubCommand::with_name(CMD_TIME_ENTRY)
                 .alias("te")
-                .about("Creates an time entry"),
+                .about("Creates an time entry")
+                .arg(
+                    Arg::with_name(ARG_ACTIVITY)
+                        .help("Defines the activity id, name or alias. If no activity is passed the default activity of the settings will be taken or, on a terminal, an activity can be picked.")
+                        .required(false)
+                )
+                .arg(
+                    Arg::with_name(ARG_FROM)
+                        .help("Defines the start of the time entry, e.g. \"09:30\", \"2021-06-01 09:30\" or \"-1h\"")
+                        .long(ARG_FROM)
+                        .short("f")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .required(true)
+                )
+                .arg(
+                    Arg::with_name(ARG_TO)
+                        .help("Defines the end of the time entry. If no end is provided the current time will be taken.")
+                        .long(ARG_TO)
+                        .short("t")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .required(false)
+                )
+                .arg(
+                    Arg::with_name(ARG_NOTE)
+                        .help("Defines a note for the time entry, #tag and @mention are referenced")
+                        .long(ARG_NOTE)
+                        .short("n")
+                        .takes_value(true)
+                        .required(false)
+                )
+                .arg(create_tags_arg())
         )
 }
 
-pub fn handle_match<'a>(matches: &ArgMatches<'a>, tmlr: &Timeular) -> Result<()> {
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+) -> Result<()> {
     if let (sub_cmd, Some(sub_matches)) = matches.subcommand() {
         match sub_cmd {
-            CMD_ACTIVITY => handle_create_activity(tmlr, sub_matches),
-            CMD_TAG => handle_create_tag(tmlr, sub_matches),
-            CMD_TIME_ENTRY => {
-                println!("Not implemented yet!");
-                Ok(())
-            }
+            CMD_ACTIVITY => handle_create_activity(tmlr, sub_matches, cfg),
+            CMD_TAG => handle_create_tag(tmlr, sub_matches, cfg),
+            CMD_TIME_ENTRY => handle_create_time_entry(tmlr, sub_matches, cfg),
             _ => {
                 println!("{}", matches.usage());
                 Err(InvalidCommandError)
@@ -109,14 +159,21 @@ pub fn handle_match<'a>(matches: &ArgMatches<'a>, tmlr: &Timeular) -> Result<()>
     }
 }
 
-fn handle_create_activity<'a>(tmlr: &Timeular, matches: &ArgMatches<'a>) -> Result<()> {
+fn handle_create_activity<'a>(
+    tmlr: &Timeular,
+    matches: &ArgMatches<'a>,
+    cfg: Option<&Settings>,
+) -> Result<()> {
     let (ac_id, name) = tmlr.create_activity(
         matches
             .value_of(ARG_ACTIVITY_NAME)
             .map(|v| v.to_string())
             .expect("An activity name was provided"),
-        matches.value_of(ARG_ACTIVITY_COLOR).map(|v| v.to_string()),
-        matches.value_of(ARG_SPACE_ID).map(|v| v.to_string()),
+        match matches.value_of(ARG_ACTIVITY_COLOR) {
+            Some(v) => Some(parse_color(v)?),
+            None => None,
+        },
+        space_id(matches, cfg),
     )?;
     log::info!("Activity \"{}\" was created.", name);
 
@@ -127,20 +184,92 @@ fn handle_create_activity<'a>(tmlr: &Timeular, matches: &ArgMatches<'a>) -> Resu
     Ok(())
 }
 
-fn handle_create_tag<'a>(tmlr: &Timeular, matches: &ArgMatches<'a>) -> Result<()> {
-    let tag_id = tmlr.create_tag(
+fn handle_create_tag<'a>(
+    tmlr: &Timeular,
+    matches: &ArgMatches<'a>,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let tag = tmlr.create_tag(
         matches
             .value_of(ARG_LABEL)
             .map(|v| v.to_string())
             .expect("An tag label was provided"),
         matches.value_of(ARG_KEY).map(|v| v.to_string()),
-        matches.value_of(ARG_SPACE_ID).map(|v| v.to_string()),
+        space_id(matches, cfg),
     )?;
     log::info!("Tag created.");
 
     if let Some(alias) = matches.value_of(ARG_ALIAS) {
-        add_tag_alias(alias, &tag_id.to_string(), matches.value_of(ARG_CONFIG))?;
+        add_tag_alias(alias, &tag.id.to_string(), matches.value_of(ARG_CONFIG))?;
     }
 
     Ok(())
 }
+
+fn handle_create_time_entry<'a>(
+    tmlr: &Timeular,
+    matches: &ArgMatches<'a>,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let defaults = cfg.and_then(|c| c.defaults.clone()).unwrap_or_default();
+    let now = Local::now();
+    let from = parse_time(
+        matches
+            .value_of(ARG_FROM)
+            .expect("A start of the time entry was provided"),
+        now,
+    )?;
+    let to = parse_time(matches.value_of(ARG_TO).unwrap_or("now"), now)?;
+    check_time_range(from, to)?;
+
+    let resolver = Resolver::new(tmlr, cfg);
+    let activity = match matches
+        .value_of(ARG_ACTIVITY)
+        .map(|v| v.to_string())
+        .or_else(|| defaults.activity.clone())
+    {
+        Some(v) => resolver.activity(&v)?,
+        None => match pick_activity(tmlr, cfg)? {
+            Some(v) => v,
+            None => {
+                println!("{}", matches.usage());
+                return Err(InvalidCommandError);
+            }
+        },
+    };
+    let note = create_note(
+        &resolver,
+        matches.value_of(ARG_NOTE).map(|v| v.to_string()),
+        &defaults,
+        matches.is_present(ARG_CREATE_TAGS),
+    )?;
+
+    let result = tmlr.create_time_entry(&activity.id, from, to, note.clone());
+    let recorded = || Operation::CreateTimeEntry {
+        activity_id: activity.id.to_owned(),
+        activity_name: activity.name.to_owned(),
+        started_at: from,
+        stopped_at: to,
+        note,
+    };
+    if record_offline(result, recorded)?.is_none() {
+        return Ok(());
+    }
+    log::info!(
+        "Time entry for activity \"{}\" with {} was created.",
+        activity.name,
+        format_duration(to - from)
+    );
+    Ok(())
+}
+
+/// Takes the space id of the arguments or falls back to the default space of the settings
+fn space_id<'a>(matches: &ArgMatches<'a>, cfg: Option<&Settings>) -> Option<String> {
+    matches
+        .value_of(ARG_SPACE_ID)
+        .map(|v| v.to_string())
+        .or_else(|| {
+            cfg.and_then(|c| c.defaults.as_ref())
+                .and_then(|d| d.space.clone())
+        })
+}
@@ -0,0 +1,64 @@
+use crate::{
+    daemon::{server, DaemonClient},
+    timeular::Timeular,
+    util::time::format_duration,
+    Result,
+};
+use chrono::Duration;
+use clap::{App, Arg, ArgMatches};
+
+pub const CMD_DAEMON: &str = "daemon";
+
+const ARG_STOP: &str = "stop";
+const ARG_STATUS: &str = "status";
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_DAEMON)
+        .about(
+            "Runs in the foreground and keeps the session, other commands use it to skip the login",
+        )
+        .arg(
+            Arg::with_name(ARG_STOP)
+                .help("Stops the running daemon")
+                .long(ARG_STOP)
+                .conflicts_with(ARG_STATUS),
+        )
+        .arg(
+            Arg::with_name(ARG_STATUS)
+                .help("Shows whether a daemon is running")
+                .long(ARG_STATUS),
+        )
+}
+
+/// Whether the command only talks to a running daemon and needs no session of its own
+pub fn is_control<'a>(matches: &ArgMatches<'a>) -> bool {
+    matches.is_present(ARG_STOP) || matches.is_present(ARG_STATUS)
+}
+
+pub fn handle_control<'a>(matches: &ArgMatches<'a>) -> Result<()> {
+    let daemon = match DaemonClient::connect() {
+        Ok(v) => v,
+        Err(e) => {
+            log::debug!("{}", e);
+            println!("No daemon is running.");
+            return Ok(());
+        }
+    };
+
+    if matches.is_present(ARG_STOP) {
+        daemon.shutdown()?;
+        log::info!("Daemon stopped.");
+    } else {
+        let ping = daemon.ping()?;
+        println!(
+            "Daemon is running (pid {}, up {}).",
+            ping.pid,
+            format_duration(Duration::seconds(ping.uptime_secs as i64))
+        );
+    }
+    Ok(())
+}
+
+pub fn handle_match(tmlr: &Timeular) -> Result<()> {
+    server::run(tmlr)
+}
@@ -0,0 +1,333 @@
+use super::{
+    create::{ARG_ACTIVITY_COLOR, ARG_ACTIVITY_NAME, ARG_KEY, ARG_LABEL},
+    note::{create_note, create_tags_arg},
+    resolver::Resolver,
+    time_entry::{check_overlap, check_time_range, find_time_entry, format_range},
+    ARG_ACTIVITY, ARG_CREATE_TAGS, ARG_FROM, ARG_NOTE, ARG_TO, CMD_ACTIVITY, CMD_MENTION, CMD_TAG,
+    CMD_TIME_ENTRY,
+};
+use crate::{
+    error::Error::InvalidCommandError,
+    settings::{Defaults, Settings},
+    timeular::Timeular,
+    util::{
+        color::parse_color,
+        time::{parse_duration, parse_time_on},
+    },
+    Result,
+};
+use chrono::Local;
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+pub const CMD_EDIT: &str = "edit";
+
+const ARG_TAG: &str = "tag";
+const ARG_MENTION: &str = "mention";
+const ARG_TIME_ENTRY: &str = "timeEntry";
+const ARG_SHIFT: &str = "shift";
+const ARG_ALLOW_OVERLAP: &str = "allow-overlap";
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_EDIT)
+        .about("Edit Resources")
+        .subcommand(
+            SubCommand::with_name(CMD_ACTIVITY)
+                .alias("ac")
+                .about("Edits an activity")
+                .arg(
+                    Arg::with_name(ARG_ACTIVITY)
+                        .help("Defines the activity id, name or alias")
+                        .required(true)
+                )
+                .arg(
+                    Arg::with_name(ARG_ACTIVITY_NAME)
+                        .help("Defines the new name of the activity")
+                        .long(ARG_ACTIVITY_NAME)
+                        .takes_value(true)
+                        .required(false)
+                )
+                .arg(
+                    Arg::with_name(ARG_ACTIVITY_COLOR)
+                        .help("Defines the new color of the activity as hex value (e.g. #1a2b3c), CSS color name or \"random\"")
+                        .long(ARG_ACTIVITY_COLOR)
+                        .short("c")
+                        .takes_value(true)
+                        .required(false)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name(CMD_TAG)
+                .about("Edits a tag")
+                .arg(
+                    Arg::with_name(ARG_TAG)
+                        .help("Defines the tag id, label or alias")
+                        .required(true)
+                )
+                .args(&tnm_args())
+        )
+        .subcommand(
+            SubCommand::with_name(CMD_MENTION)
+                .about("Edits a mention")
+                .arg(
+                    Arg::with_name(ARG_MENTION)
+                        .help("Defines the mention id, label or alias")
+                        .required(true)
+                )
+                .args(&tnm_args())
+        )
+        .subcommand(
+            SubCommand::with_name(CMD_TIME_ENTRY)
+                .alias("te")
+                .about("Edits a time entry")
+                .arg(
+                    Arg::with_name(ARG_TIME_ENTRY)
+                        .help("Defines the time entry id or \"last\" for the most recent time entry")
+                        .required(true)
+                )
+                .arg(
+                    Arg::with_name(ARG_ACTIVITY)
+                        .help("Defines the new activity id, name or alias")
+                        .long(ARG_ACTIVITY)
+                        .short("a")
+                        .takes_value(true)
+                        .required(false)
+                )
+                .arg(
+                    Arg::with_name(ARG_FROM)
+                        .help("Defines the new start, e.g. \"09:30\", \"2021-06-01 09:30\" or \"-1h\"")
+                        .long(ARG_FROM)
+                        .short("f")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .required(false)
+                )
+                .arg(
+                    Arg::with_name(ARG_TO)
+                        .help("Defines the new end")
+                        .long(ARG_TO)
+                        .short("t")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .required(false)
+                )
+                .arg(
+                    Arg::with_name(ARG_SHIFT)
+                        .help("Moves the whole time entry by the given duration, e.g. \"+15m\" or \"-1h\"")
+                        .long(ARG_SHIFT)
+                        .short("s")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .conflicts_with_all(&[ARG_FROM, ARG_TO])
+                        .required(false)
+                )
+                .arg(
+                    Arg::with_name(ARG_NOTE)
+                        .help("Replaces the note of the time entry, #tag and @mention are referenced")
+                        .long(ARG_NOTE)
+                        .short("n")
+                        .takes_value(true)
+                        .required(false)
+                )
+                .arg(create_tags_arg())
+                .arg(
+                    Arg::with_name(ARG_ALLOW_OVERLAP)
+                        .help("Saves the time entry even if it overlaps with other time entries")
+                        .long(ARG_ALLOW_OVERLAP)
+                )
+        )
+}
+
+fn tnm_args<'a, 'b>() -> [Arg<'a, 'b>; 2] {
+    [
+        Arg::with_name(ARG_LABEL)
+            .help("Defines the new label")
+            .long(ARG_LABEL)
+            .short("l")
+            .takes_value(true)
+            .required(false),
+        Arg::with_name(ARG_KEY)
+            .help("Defines the new key, which is used to reference it in notes")
+            .long(ARG_KEY)
+            .short("k")
+            .takes_value(true)
+            .required(false),
+    ]
+}
+
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    match matches.subcommand() {
+        (CMD_ACTIVITY, Some(sub_matches)) => handle_edit_activity(tmlr, sub_matches, cfg),
+        (CMD_TAG, Some(sub_matches)) => handle_edit_tag(tmlr, sub_matches, cfg),
+        (CMD_MENTION, Some(sub_matches)) => handle_edit_mention(tmlr, sub_matches, cfg),
+        (CMD_TIME_ENTRY, Some(sub_matches)) => handle_edit_time_entry(tmlr, sub_matches, cfg),
+        _ => {
+            println!("{}", matches.usage());
+            Err(InvalidCommandError)
+        }
+    }
+}
+
+fn handle_edit_activity<'a>(
+    tmlr: &Timeular,
+    matches: &ArgMatches<'a>,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let name = matches.value_of(ARG_ACTIVITY_NAME).map(|v| v.to_string());
+    let color = match matches.value_of(ARG_ACTIVITY_COLOR) {
+        Some(v) => Some(parse_color(v)?),
+        None => None,
+    };
+
+    if name.is_none() && color.is_none() {
+        log::info!("Nothing to edit, please provide a new name or color.");
+        println!("{}", matches.usage());
+        return Err(InvalidCommandError);
+    }
+
+    let activity = Resolver::new(tmlr, cfg).activity(
+        matches
+            .value_of(ARG_ACTIVITY)
+            .expect("An activity was provided"),
+    )?;
+    let edited = tmlr.edit_activity(&activity.id, name, color)?;
+    log::info!("Activity \"{}\" was edited.", edited.name);
+
+    Ok(())
+}
+
+fn handle_edit_tag<'a>(
+    tmlr: &Timeular,
+    matches: &ArgMatches<'a>,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let (label, key) = match label_and_key(matches) {
+        Some(v) => v,
+        None => return Err(InvalidCommandError),
+    };
+
+    let tag =
+        Resolver::new(tmlr, cfg).tag(matches.value_of(ARG_TAG).expect("A tag was provided"))?;
+    let edited = tmlr.edit_tag(tag.id, label, key)?;
+    log::info!("Tag \"{}\" was edited.", edited.label);
+
+    Ok(())
+}
+
+fn handle_edit_mention<'a>(
+    tmlr: &Timeular,
+    matches: &ArgMatches<'a>,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let (label, key) = match label_and_key(matches) {
+        Some(v) => v,
+        None => return Err(InvalidCommandError),
+    };
+
+    let mention = Resolver::new(tmlr, cfg).mention(
+        matches
+            .value_of(ARG_MENTION)
+            .expect("A mention was provided"),
+    )?;
+    let edited = tmlr.edit_mention(mention.id, label, key)?;
+    log::info!("Mention \"{}\" was edited.", edited.label);
+
+    Ok(())
+}
+
+fn handle_edit_time_entry<'a>(
+    tmlr: &Timeular,
+    matches: &ArgMatches<'a>,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    // --create-tags only modifies --note, so it's nothing to edit on its own
+    if ![ARG_ACTIVITY, ARG_FROM, ARG_TO, ARG_SHIFT, ARG_NOTE]
+        .iter()
+        .any(|a| matches.is_present(a))
+    {
+        log::info!("Nothing to edit, please provide a new activity, start, end, shift or note.");
+        println!("{}", matches.usage());
+        return Err(InvalidCommandError);
+    }
+
+    let entry = find_time_entry(
+        tmlr,
+        matches
+            .value_of(ARG_TIME_ENTRY)
+            .expect("A time entry was provided"),
+    )?;
+    // Bare times are on the day of the entry, not today
+    let now = Local::now();
+    let day = entry
+        .duration
+        .started_at
+        .with_timezone(&Local)
+        .date()
+        .naive_local();
+    let mut from = match matches.value_of(ARG_FROM) {
+        Some(v) => parse_time_on(v, now, day)?,
+        None => entry.duration.started_at,
+    };
+    let mut to = match matches.value_of(ARG_TO) {
+        Some(v) => parse_time_on(v, now, day)?,
+        None => entry.duration.stopped_at,
+    };
+    if let Some(v) = matches.value_of(ARG_SHIFT) {
+        let shift = parse_duration(v)?;
+        from = from + shift;
+        to = to + shift;
+    }
+    check_time_range(from, to)?;
+
+    if !matches.is_present(ARG_ALLOW_OVERLAP) {
+        if let Err(e) = check_overlap(tmlr, &[&entry.id], from, to) {
+            log::info!("Use --{} to save it anyway.", ARG_ALLOW_OVERLAP);
+            return Err(e);
+        }
+    }
+
+    let resolver = Resolver::new(tmlr, cfg);
+    let activity_id = match matches.value_of(ARG_ACTIVITY) {
+        Some(v) => resolver.activity(v)?.id,
+        None => entry.activity_id.to_owned(),
+    };
+    let note = match matches.value_of(ARG_NOTE) {
+        Some(v) => create_note(
+            &resolver,
+            Some(v.to_owned()),
+            &Defaults {
+                space: cfg
+                    .and_then(|c| c.defaults.as_ref())
+                    .and_then(|d| d.space.clone()),
+                ..Defaults::default()
+            },
+            matches.is_present(ARG_CREATE_TAGS),
+        )?,
+        None => entry.note.clone().unwrap_or_default(),
+    };
+
+    tmlr.edit_time_entry(&entry.id, &activity_id, from, to, note)?;
+    log::info!(
+        "Time entry {} was edited ({}).",
+        entry.id,
+        format_range(from, to)
+    );
+    Ok(())
+}
+
+/// Reads the new label and key, prints the usage if none of them is given. Aliases don't need
+/// to be touched as they point to the id which stays the same.
+fn label_and_key<'a>(matches: &ArgMatches<'a>) -> Option<(Option<String>, Option<String>)> {
+    let label = matches.value_of(ARG_LABEL).map(|v| v.to_string());
+    let key = matches.value_of(ARG_KEY).map(|v| v.to_string());
+
+    if label.is_none() && key.is_none() {
+        log::info!("Nothing to edit, please provide a new label or key.");
+        println!("{}", matches.usage());
+        return None;
+    }
+    Some((label, key))
+}
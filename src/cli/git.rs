@@ -0,0 +1,458 @@
+use super::{note::create_note, resolver::Resolver};
+use crate::{
+    error::Error::{GitError, InvalidCommandError},
+    settings::{BranchMapping, Defaults, Settings},
+    timeular::{CurrentTrackingResponse, Note, TimeEntryResponse, Timeular},
+    util::{
+        git::{current_branch, head_commit_time, hooks_dir},
+        time::format_duration,
+    },
+    Result,
+};
+use chrono::{DateTime, Duration, Local, Utc};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use glob::Pattern;
+use std::{
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+pub const CMD_GIT: &str = "git";
+pub const CMD_INSTALL_HOOKS: &str = "install-hooks";
+const CMD_POST_CHECKOUT: &str = "post-checkout";
+const CMD_PREPARE_COMMIT_MSG: &str = "prepare-commit-msg";
+
+const ARG_FORCE: &str = "force";
+const ARG_PREVIOUS: &str = "previous";
+const ARG_NEXT: &str = "next";
+const ARG_BRANCH_FLAG: &str = "branch-flag";
+const ARG_FILE: &str = "file";
+const ARG_SOURCE: &str = "source";
+const ARG_COMMIT: &str = "commit";
+
+/// Identifies hooks which may be overwritten
+const HOOK_MARKER: &str = "# Installed by tmlr";
+const TRAILER: &str = "Tracked-Time";
+
+/// Commit messages which are not written for a new commit
+const SKIPPED_SOURCES: [&str; 3] = ["merge", "squash", "commit"];
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    let hook = |name| {
+        SubCommand::with_name(name)
+            .setting(AppSettings::Hidden)
+            .about("Called by the git hook")
+    };
+
+    App::new(CMD_GIT)
+        .about("Tracks the activity mapped to the checked out branch and annotates commits")
+        .subcommand(
+            SubCommand::with_name(CMD_INSTALL_HOOKS)
+                .about("Installs the post-checkout and prepare-commit-msg hooks in the current repository")
+                .arg(
+                    Arg::with_name(ARG_FORCE)
+                        .help("Overwrites existing hooks which were not installed by tmlr")
+                        .long(ARG_FORCE)
+                        .short("f"),
+                ),
+        )
+        .subcommand(
+            hook(CMD_POST_CHECKOUT)
+                .arg(Arg::with_name(ARG_PREVIOUS).required(true))
+                .arg(Arg::with_name(ARG_NEXT).required(true))
+                .arg(Arg::with_name(ARG_BRANCH_FLAG).required(true)),
+        )
+        .subcommand(
+            hook(CMD_PREPARE_COMMIT_MSG)
+                .arg(Arg::with_name(ARG_FILE).required(true))
+                .arg(Arg::with_name(ARG_SOURCE))
+                .arg(Arg::with_name(ARG_COMMIT)),
+        )
+}
+
+/// Installing the hooks needs no session
+pub fn is_install<'a>(matches: &ArgMatches<'a>) -> bool {
+    matches.subcommand_name() == Some(CMD_INSTALL_HOOKS)
+}
+
+/// Whether the hook has nothing to do. Hooks run on every checkout and commit, so this is
+/// checked before logging in.
+pub fn is_idle_hook<'a>(matches: &ArgMatches<'a>, cfg: Option<&Settings>) -> Result<bool> {
+    match matches.subcommand_name() {
+        Some(CMD_POST_CHECKOUT) | Some(CMD_PREPARE_COMMIT_MSG) => {
+            Ok(hook_mapping(matches, cfg)?.is_none())
+        }
+        _ => Ok(false),
+    }
+}
+
+pub fn handle_install<'a>(matches: &ArgMatches<'a>) -> Result<()> {
+    let force = matches!(
+        matches.subcommand_matches(CMD_INSTALL_HOOKS),
+        Some(m) if m.is_present(ARG_FORCE)
+    );
+    for path in install_hooks(&current_dir()?, force)? {
+        log::info!("Installed {}.", path.display());
+    }
+    Ok(())
+}
+
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let mapping = hook_mapping(matches, cfg)?;
+    match (matches.subcommand(), mapping) {
+        ((CMD_POST_CHECKOUT, Some(_)), Some((branch, mapping))) => {
+            switch_to_branch(tmlr, cfg, &branch, mapping)
+        }
+        ((CMD_PREPARE_COMMIT_MSG, Some(sub_matches)), Some((_, mapping))) => annotate_commit(
+            tmlr,
+            cfg,
+            mapping,
+            Path::new(sub_matches.value_of(ARG_FILE).unwrap_or_default()),
+        ),
+        ((CMD_POST_CHECKOUT, Some(_)), None) | ((CMD_PREPARE_COMMIT_MSG, Some(_)), None) => Ok(()),
+        _ => {
+            println!("{}", matches.usage());
+            Err(InvalidCommandError)
+        }
+    }
+}
+
+/// Writes the hooks, existing hooks are only replaced if they were installed by tmlr or with
+/// `force`. Returns the paths of the hooks.
+fn install_hooks(repo: &Path, force: bool) -> Result<Vec<PathBuf>> {
+    let dir = hooks_dir(repo)?;
+    fs::create_dir_all(&dir).map_err(|e| GitError(e.to_string()))?;
+
+    let hooks = [CMD_POST_CHECKOUT, CMD_PREPARE_COMMIT_MSG];
+    let paths: Vec<PathBuf> = hooks.iter().map(|h| dir.join(h)).collect();
+    for path in paths.iter() {
+        let is_foreign = fs::read_to_string(path)
+            .map(|content| !content.contains(HOOK_MARKER))
+            .unwrap_or(false);
+        if is_foreign && !force {
+            return Err(GitError(format!(
+                "{} exists, use --{} to overwrite it",
+                path.display(),
+                ARG_FORCE
+            )));
+        }
+    }
+
+    for (hook, path) in hooks.iter().zip(paths.iter()) {
+        let script = format!(
+            "#!/bin/sh\n{}\ncommand -v tmlr >/dev/null 2>&1 || exit 0\ntmlr {} {} \"$@\" || true\n",
+            HOOK_MARKER, CMD_GIT, hook
+        );
+        fs::write(path, script).map_err(|e| GitError(e.to_string()))?;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+            .map_err(|e| GitError(e.to_string()))?;
+    }
+    Ok(paths)
+}
+
+/// Returns the checked out branch and its mapping if the hook has something to do with them
+fn hook_mapping<'a, 'c>(
+    matches: &ArgMatches<'a>,
+    cfg: Option<&'c Settings>,
+) -> Result<Option<(String, &'c BranchMapping)>> {
+    match matches.subcommand() {
+        (CMD_POST_CHECKOUT, Some(sub_matches)) => {
+            // Checkouts of files don't change the branch
+            if sub_matches.value_of(ARG_BRANCH_FLAG) != Some("1") {
+                return Ok(None);
+            }
+        }
+        (CMD_PREPARE_COMMIT_MSG, Some(sub_matches)) => {
+            let source = sub_matches.value_of(ARG_SOURCE).unwrap_or_default();
+            let is_enabled = cfg
+                .and_then(|c| c.git.as_ref())
+                .and_then(|g| g.append_time)
+                .unwrap_or(false);
+            if !is_enabled || SKIPPED_SOURCES.contains(&source) {
+                return Ok(None);
+            }
+        }
+        _ => return Ok(None),
+    }
+
+    let branch = match current_branch(&current_dir()?)? {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    match find_mapping(cfg, &branch) {
+        Some(v) => Ok(Some((branch, v))),
+        None => {
+            log::debug!("No activity is mapped to branch {}", branch);
+            Ok(None)
+        }
+    }
+}
+
+/// Switches the tracking to the activity mapped to the checked out branch
+fn switch_to_branch(
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+    branch: &str,
+    mapping: &BranchMapping,
+) -> Result<()> {
+    let resolver = Resolver::new(tmlr, cfg);
+    let activity = resolver.activity(&mapping.activity)?;
+    if let Some(tracking) = tmlr.current_tracking()? {
+        if tracking.activity_id == activity.id {
+            return Ok(());
+        }
+    }
+
+    let note = match &mapping.mention {
+        Some(m) => create_note(
+            &resolver,
+            Some(format!("@{}", m)),
+            &Defaults::default(),
+            false,
+        )?,
+        None => Note::default(),
+    };
+    tmlr.switch_tracking(&activity.id, Some(note))?;
+    log::info!("Tracking \"{}\" for branch {}.", activity.name, branch);
+    Ok(())
+}
+
+/// Appends the time tracked for the branch since the last commit to the commit message
+fn annotate_commit(
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+    mapping: &BranchMapping,
+    file: &Path,
+) -> Result<()> {
+    let dir = current_dir()?;
+    let resolver = Resolver::new(tmlr, cfg);
+    let activity = resolver.activity(&mapping.activity)?;
+    let mention_id = match &mapping.mention {
+        Some(m) => Some(resolver.mention(m)?.id),
+        None => None,
+    };
+
+    let now = Utc::now();
+    // Without a commit the time of today is taken
+    let since = match head_commit_time(&dir)? {
+        Some(v) => v,
+        None => Local::today().and_hms(0, 0, 0).with_timezone(&Utc),
+    };
+    let entries = tmlr.list_time_entries(since, now)?;
+    let tracked = tracked_time(
+        &entries,
+        tmlr.current_tracking()?.as_ref(),
+        &activity.id,
+        mention_id,
+        since,
+        now,
+    );
+    if tracked < Duration::minutes(1) {
+        return Ok(());
+    }
+
+    let message = fs::read_to_string(file).map_err(|e| GitError(e.to_string()))?;
+    if let Some(v) = append_trailer(&message, tracked) {
+        fs::write(file, v).map_err(|e| GitError(e.to_string()))?;
+    }
+    Ok(())
+}
+
+fn current_dir() -> Result<PathBuf> {
+    env::current_dir().map_err(|e| GitError(e.to_string()))
+}
+
+fn find_mapping<'a>(cfg: Option<&'a Settings>, branch: &str) -> Option<&'a BranchMapping> {
+    cfg.and_then(|c| c.git.as_ref())
+        .and_then(|g| g.branches.as_ref())
+        .and_then(|branches| {
+            branches.iter().find(|m| match Pattern::new(&m.pattern) {
+                Ok(p) => p.matches(branch),
+                Err(e) => {
+                    log::debug!("Invalid branch pattern {}: {}", m.pattern, e);
+                    false
+                }
+            })
+        })
+}
+
+/// Sums up the time of the activity (with the mention) between `since` and `now`, including the
+/// running tracking
+fn tracked_time(
+    entries: &[TimeEntryResponse],
+    running: Option<&CurrentTrackingResponse>,
+    activity_id: &str,
+    mention_id: Option<i64>,
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Duration {
+    let is_counted = |id: &str, note: Option<&Note>| {
+        id == activity_id
+            && match mention_id {
+                Some(m) => matches!(note, Some(n) if n.mentions.iter().any(|r| r.id == Some(m))),
+                None => true,
+            }
+    };
+    let clipped = |from: DateTime<Utc>, to: DateTime<Utc>| {
+        (to.min(now) - from.max(since)).max(Duration::zero())
+    };
+
+    let mut total = entries
+        .iter()
+        .filter(|e| is_counted(&e.activity_id, e.note.as_ref()))
+        .map(|e| clipped(e.duration.started_at, e.duration.stopped_at))
+        .fold(Duration::zero(), |acc, d| acc + d);
+    if let Some(t) = running.filter(|t| is_counted(&t.activity_id, t.note.as_ref())) {
+        total = total + clipped(t.started_at, now);
+    }
+    total
+}
+
+/// Adds the trailer in front of the comments git puts below the message. Returns `None` if the
+/// message already has the trailer.
+fn append_trailer(message: &str, tracked: Duration) -> Option<String> {
+    if message
+        .lines()
+        .any(|l| l.starts_with(&format!("{}:", TRAILER)))
+    {
+        return None;
+    }
+
+    let lines: Vec<&str> = message.lines().collect();
+    let comments_start = lines
+        .iter()
+        .rposition(|l| !l.starts_with('#') && !l.trim().is_empty())
+        .map_or(0, |idx| idx + 1);
+    let (text, comments) = lines.split_at(comments_start);
+
+    let mut result = text.join("\n");
+    result.push_str(&format!("\n\n{}: {}\n", TRAILER, format_duration(tracked)));
+    for line in comments {
+        result.push_str(line);
+        result.push('\n');
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{settings::Git, timeular::NoteReference, util::git::tests::init_repo};
+    use chrono::TimeZone;
+
+    fn at(hour: u32, min: u32) -> DateTime<Utc> {
+        Utc.ymd(2021, 6, 1).and_hms(hour, min, 0)
+    }
+
+    fn mention(id: i64) -> Note {
+        Note {
+            text: None,
+            tags: vec![],
+            mentions: vec![NoteReference {
+                id: Some(id),
+                key: String::new(),
+                label: None,
+                indices: [0, 0],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_find_mapping() {
+        let mapping = |pattern: &str, activity: &str| BranchMapping {
+            pattern: pattern.to_owned(),
+            activity: activity.to_owned(),
+            mention: None,
+        };
+        let cfg = Settings {
+            git: Some(Git {
+                append_time: None,
+                branches: Some(vec![
+                    mapping("PROJ-*", "dev"),
+                    mapping("[", "broken"),
+                    mapping("*", "other"),
+                ]),
+            }),
+            ..Settings::default()
+        };
+
+        assert_eq!(
+            find_mapping(Some(&cfg), "PROJ-123-login").map(|m| m.activity.as_str()),
+            Some("dev")
+        );
+        assert_eq!(
+            find_mapping(Some(&cfg), "main").map(|m| m.activity.as_str()),
+            Some("other")
+        );
+        assert!(find_mapping(None, "main").is_none());
+    }
+
+    #[test]
+    fn test_tracked_time() {
+        let entries = [
+            TimeEntryResponse::new("", "1", at(8, 0), at(10, 0)),
+            TimeEntryResponse::new("", "1", at(11, 0), at(11, 30)).with_note(mention(7)),
+            TimeEntryResponse::new("", "2", at(12, 0), at(13, 0)).with_note(mention(7)),
+        ];
+        let running = CurrentTrackingResponse {
+            activity_id: "1".to_owned(),
+            started_at: at(14, 0),
+            note: None,
+        };
+
+        let tracked = tracked_time(&entries, Some(&running), "1", None, at(9, 0), at(14, 15));
+        assert_eq!(tracked, Duration::minutes(105));
+
+        let tracked = tracked_time(&entries, Some(&running), "1", Some(7), at(9, 0), at(14, 15));
+        assert_eq!(tracked, Duration::minutes(30));
+    }
+
+    #[test]
+    fn test_append_trailer() {
+        let message = "Add login\n\n# Please enter the commit message\n# with comments\n";
+        assert_eq!(
+            append_trailer(message, Duration::minutes(65)).unwrap(),
+            "Add login\n\nTracked-Time: 1h 05m\n\n# Please enter the commit message\n# with comments\n"
+        );
+        assert_eq!(
+            append_trailer(
+                "\n# Please enter the commit message\n",
+                Duration::minutes(5)
+            )
+            .unwrap(),
+            "\n\nTracked-Time: 0h 05m\n\n# Please enter the commit message\n"
+        );
+        assert!(append_trailer("Fix\n\nTracked-Time: 0h 05m\n", Duration::minutes(5)).is_none());
+    }
+
+    #[test]
+    fn test_install_hooks() {
+        let repo = init_repo("hooks");
+        let paths = install_hooks(&repo, false).unwrap();
+        assert_eq!(paths.len(), 2);
+        for path in paths.iter() {
+            let content = fs::read_to_string(path).unwrap();
+            assert!(content.contains(HOOK_MARKER));
+            assert!(content.contains(&format!(
+                "tmlr git {}",
+                path.file_name().unwrap().to_string_lossy()
+            )));
+            assert_eq!(
+                fs::metadata(path).unwrap().permissions().mode() & 0o777,
+                0o755
+            );
+        }
+        // The own hooks are replaced
+        assert!(install_hooks(&repo, false).is_ok());
+
+        fs::write(&paths[0], "#!/bin/sh\necho custom\n").unwrap();
+        assert!(install_hooks(&repo, false).is_err());
+        assert!(install_hooks(&repo, true).is_ok());
+        fs::remove_dir_all(&repo).unwrap();
+    }
+}
@@ -1,20 +1,98 @@
-use super::{CMD_ACTIVITY, CMD_MENTION, CMD_TAG};
-use crate::Result;
-use clap::{App, ArgMatches, SubCommand};
+use super::{picker::activity_aliases, CMD_ACTIVITY, CMD_MENTION, CMD_TAG};
+use crate::{
+    settings::Settings,
+    timeular::{ActivityResponse, Timeular},
+    util::color::ansi_foreground,
+    Result,
+};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::collections::HashMap;
 
 pub const CMD_LIST: &str = "list";
 pub const CMD_SPACE: &str = "space";
 
+const ARG_ARCHIVED: &str = "archived";
+
 pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
     App::new(CMD_LIST)
         .about("Lists Resources")
-        .subcommand(SubCommand::with_name(CMD_ACTIVITY).about("Lists all Activities"))
+        .subcommand(
+            SubCommand::with_name(CMD_ACTIVITY)
+                .about("Lists all Activities")
+                .arg(
+                    Arg::with_name(ARG_ARCHIVED)
+                        .help("Includes archived activities")
+                        .long(ARG_ARCHIVED)
+                        .short("a"),
+                ),
+        )
         .subcommand(SubCommand::with_name(CMD_TAG).about("Lists all Tags"))
         .subcommand(SubCommand::with_name(CMD_MENTION).about("Lists all Mentions"))
         .subcommand(SubCommand::with_name(CMD_SPACE).about("Lists all Spaces"))
 }
 
-pub fn handle_match<'a>(_matches: &ArgMatches<'a>) -> Result<()> {
-    log::info!("Command {} not implemented", CMD_LIST);
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    match matches.subcommand() {
+        (CMD_ACTIVITY, Some(sub_matches)) => handle_list_activity(tmlr, sub_matches, cfg),
+        _ => {
+            log::info!("Command {} not implemented", CMD_LIST);
+            Ok(())
+        }
+    }
+}
+
+fn handle_list_activity<'a>(
+    tmlr: &Timeular,
+    matches: &ArgMatches<'a>,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let list = tmlr.list_activities()?;
+    let aliases = activity_aliases(cfg);
+
+    let mut rows = Vec::new();
+    rows.extend(list.activities.iter().map(|a| (a, "")));
+    rows.extend(list.inactive_activities.iter().map(|a| (a, "inactive")));
+    if matches.is_present(ARG_ARCHIVED) {
+        rows.extend(list.archived_activities.iter().map(|a| (a, "archived")));
+    }
+
+    let width = rows.iter().map(|(a, _)| a.name.chars().count()).max();
+    for (activity, state) in rows {
+        println!(
+            "{}",
+            format_activity(activity, state, &aliases, width.unwrap_or_default())
+        );
+    }
     Ok(())
 }
+
+fn format_activity(
+    activity: &ActivityResponse,
+    state: &str,
+    aliases: &HashMap<String, Vec<String>>,
+    width: usize,
+) -> String {
+    let side = match activity.device_side {
+        Some(v) => format!("side {}", v),
+        None => "-".to_owned(),
+    };
+    let mut line = format!(
+        "{} {:width$}  {:>10}  {:6}",
+        ansi_foreground(&activity.color, "●"),
+        activity.name,
+        activity.id,
+        side,
+        width = width
+    );
+    if let Some(v) = aliases.get(&activity.id) {
+        line.push_str(&format!("  ({})", v.join(", ")));
+    }
+    if !state.is_empty() {
+        line.push_str(&format!("  [{}]", state));
+    }
+    line.trim_end().to_owned()
+}
@@ -1,16 +1,21 @@
 use crate::{
-    cli::config::{self as cli_config, ARG_API_KEY, ARG_API_SECRET, ARG_CONFIG},
-    error::Error::{AuthenticationInformationMissingError, InvalidCommandError},
+    cli::{
+        cache::{self as cli_cache, ARG_REFRESH},
+        config::{self as cli_config, ARG_API_KEY, ARG_API_SECRET, ARG_CONFIG},
+    },
+    daemon::DaemonClient,
+    error::Error::{
+        ApiUnreachableError, AuthenticationInformationMissingError, InvalidCommandError,
+    },
+    journal::Journal,
     settings::Settings,
     timeular::{Timeular, TimeularAuth, TimeularCredentials},
     Result,
 };
-use clap::{App, Arg, SubCommand};
+use clap::{App, Arg, ArgMatches};
 
 const VERSION: &str = "0.1.0";
 
-const CMD_START: &str = "start";
-const CMD_STOP: &str = "stop";
 const CMD_TRACKING: &str = "tracking";
 const CMD_TIME_ENTRY: &str = "time-entry";
 const CMD_ACTIVITY: &str = "activity";
@@ -20,66 +25,46 @@ const CMD_MENTION: &str = "mention";
 const ARG_VERBOSE: &str = "verbose";
 const ARG_SPACE_ID: &str = "spaceId";
 const ARG_ALIAS: &str = "alias";
+const ARG_ACTIVITY: &str = "activity";
+const ARG_NOTE: &str = "note";
+const ARG_CREATE_TAGS: &str = "create-tags";
+const ARG_FROM: &str = "from";
+const ARG_TO: &str = "to";
 
+mod activity;
+mod audit;
+mod autotrack;
+mod balance;
+mod cache;
+mod completions;
 mod config;
 mod create;
+mod daemon;
 mod delete;
+mod edit;
+mod git;
 mod list;
+mod note;
+mod picker;
+mod pomodoro;
+mod prompt;
+mod resolver;
+mod resume;
+mod start;
+mod status;
+mod stop;
+mod switch;
+mod sync;
+mod time_entry;
+mod timesheet;
+mod watch;
 
 pub fn create_cli() -> Result<()> {
-    let app = App::new("tmlr")
-        .version(VERSION)
-        .about("Timular CLI Client")
-        .arg(
-            Arg::with_name(ARG_CONFIG)
-                .short("c")
-                .long(ARG_CONFIG)
-                .value_name("FILE")
-                .help("Sets a custom config file")
-                .takes_value(true),
-        )
-        .arg(
-            Arg::with_name(ARG_API_KEY)
-                .long(ARG_API_KEY)
-                .help("Sets an API key")
-                .takes_value(true)
-                .required(false),
-        )
-        .arg(
-            Arg::with_name(ARG_API_SECRET)
-                .long(ARG_API_SECRET)
-                .help("Sets an API secret")
-                .takes_value(true)
-                .required(false),
-        )
-        .arg(
-            Arg::with_name(ARG_VERBOSE)
-                .long(ARG_VERBOSE)
-                .short("v")
-                .help("Sets the level of verbosity"),
-        )
-        .subcommand(list::create_commands())
-        .subcommand(create::create_commands())
-        .subcommand(delete::create_commands())
-        .subcommand(cli_config::create_commands())
-        .subcommand(
-            SubCommand::with_name(CMD_START)
-                .about("Starts a Resource")
-                .subcommand(SubCommand::with_name(CMD_TRACKING)),
-        )
-        .subcommand(
-            SubCommand::with_name(CMD_STOP)
-                .about("Starts a Resource")
-                .subcommand(SubCommand::with_name(CMD_TRACKING)),
-        );
+    let app = create_app().subcommand(completions::create_complete_command());
     let matches = app.clone().get_matches();
 
     crate::util::logging::init(matches.is_present(ARG_VERBOSE));
 
-    if let Some(sub_matches) = matches.subcommand_matches(config::CMD_CONFIG) {
-        return config::handle_match(sub_matches);
-    }
-
     let cfg = match Settings::new(matches.value_of(ARG_CONFIG)) {
         Ok(v) => Some(v),
         Err(_) => {
@@ -88,6 +73,66 @@ pub fn create_cli() -> Result<()> {
         }
     };
 
+    if let Some(sub_matches) = matches.subcommand_matches(completions::CMD_COMPLETIONS) {
+        return completions::handle_match(sub_matches, create_app());
+    }
+
+    if let Some(sub_matches) = matches.subcommand_matches(completions::CMD_COMPLETE) {
+        let account = account(&matches, cfg.as_ref());
+        return completions::handle_complete(sub_matches, cfg.as_ref(), account.as_deref());
+    }
+
+    if let Some(sub_matches) = matches.subcommand_matches(prompt::CMD_PROMPT) {
+        let account = account(&matches, cfg.as_ref());
+        return prompt::handle_match(sub_matches, cfg.as_ref(), account.as_deref());
+    }
+
+    if let Some(sub_matches) = matches.subcommand_matches(prompt::CMD_INIT) {
+        return prompt::handle_init(sub_matches);
+    }
+
+    if let Some(sub_matches) = matches.subcommand_matches(cli_cache::CMD_CACHE) {
+        let account = account(&matches, cfg.as_ref());
+        return cli_cache::handle_match(sub_matches, cfg.as_ref(), account.as_deref());
+    }
+
+    if let Some(sub_matches) = matches.subcommand_matches(config::CMD_CONFIG) {
+        return config::handle_match(
+            sub_matches,
+            create_auth_data(
+                cfg.as_ref(),
+                matches.value_of(ARG_API_KEY),
+                matches.value_of(ARG_API_SECRET),
+            ),
+        );
+    }
+
+    if let Some(sub_matches) = matches.subcommand_matches(status::CMD_STATUS) {
+        if status::is_formatted(sub_matches) {
+            let auth = create_auth_data(
+                cfg.as_ref(),
+                matches.value_of(ARG_API_KEY),
+                matches.value_of(ARG_API_SECRET),
+            );
+            return status::handle_format(sub_matches, cfg.as_ref(), auth);
+        }
+    }
+
+    if let Some(sub_matches) = matches.subcommand_matches(git::CMD_GIT) {
+        if git::is_install(sub_matches) {
+            return git::handle_install(sub_matches);
+        }
+        if git::is_idle_hook(sub_matches, cfg.as_ref())? {
+            return Ok(());
+        }
+    }
+
+    if let Some(sub_matches) = matches.subcommand_matches(daemon::CMD_DAEMON) {
+        if daemon::is_control(sub_matches) {
+            return daemon::handle_control(sub_matches);
+        }
+    }
+
     match matches.subcommand() {
         (sub_cmd, Some(sub_matches)) => {
             let auth = create_auth_data(
@@ -100,19 +145,77 @@ pub fn create_cli() -> Result<()> {
                 return Err(AuthenticationInformationMissingError);
             }
 
-            let tmlr = Timeular::new(auth.expect("Auth data found"))?;
+            let auth = auth.expect("Auth data found");
+            let daemon_client = match DaemonClient::connect() {
+                Ok(v) if sub_cmd != daemon::CMD_DAEMON => Some(v),
+                Ok(_) => None,
+                Err(e) => {
+                    log::debug!("No daemon: {}", e);
+                    None
+                }
+            };
+            let session = match daemon_client {
+                Some(d) => Timeular::with_daemon(auth.clone(), d).or_else(|e| {
+                    log::debug!("Unable to use the daemon: {}", e);
+                    Timeular::new(auth.clone())
+                }),
+                None => Timeular::new(auth.clone()),
+            };
+            let mut tmlr = match session {
+                Ok(v) => v,
+                Err(ApiUnreachableError(e)) if sync::OFFLINE_COMMANDS.contains(&sub_cmd) => {
+                    log::debug!("Working offline: {}", e);
+                    Timeular::offline(auth.clone())
+                }
+                Err(e) => return Err(e),
+            };
+            if cli_cache::is_enabled(cfg.as_ref()) {
+                let cache = cli_cache::open_cache(
+                    cfg.as_ref(),
+                    Some(&auth.credentials.api_key),
+                    matches.is_present(ARG_REFRESH),
+                );
+                match cache {
+                    Ok(c) if tmlr.is_offline() => tmlr = tmlr.with_cache(c.allow_stale()),
+                    Ok(c) => tmlr = tmlr.with_cache(c),
+                    Err(e) => log::debug!("Continuing without cache: {}", e),
+                }
+            }
+            if !tmlr.is_offline() && ![sync::CMD_SYNC, status::CMD_STATUS].contains(&sub_cmd) {
+                let pending = Journal::new().load().map(|p| p.len()).unwrap_or(0);
+                if pending > 0 {
+                    log::info!(
+                        "There are {} operations recorded offline, run `tmlr {}` to send them.",
+                        pending,
+                        sync::CMD_SYNC
+                    );
+                }
+            }
 
             match sub_cmd {
-                list::CMD_LIST => list::handle_match(sub_matches),
-                create::CMD_CREATE => create::handle_match(sub_matches, &tmlr),
+                list::CMD_LIST => list::handle_match(sub_matches, &tmlr, cfg.as_ref()),
+                create::CMD_CREATE => create::handle_match(sub_matches, &tmlr, cfg.as_ref()),
                 delete::CMD_DELETE => delete::handle_match(),
-                CMD_START => {
-                    log::info!("Not implemented");
-                    Ok(())
+                CMD_ACTIVITY => activity::handle_match(sub_matches, &tmlr, cfg.as_ref()),
+                CMD_TIME_ENTRY => time_entry::handle_match(sub_matches, &tmlr, cfg.as_ref()),
+                edit::CMD_EDIT => edit::handle_match(sub_matches, &tmlr, cfg.as_ref()),
+                start::CMD_START => start::handle_match(sub_matches, &tmlr, cfg.as_ref()),
+                stop::CMD_STOP => stop::handle_match(sub_matches, &tmlr),
+                switch::CMD_SWITCH => switch::handle_match(sub_matches, &tmlr, cfg.as_ref()),
+                resume::CMD_CONTINUE => resume::handle_match(sub_matches, &tmlr, cfg.as_ref()),
+                status::CMD_STATUS => status::handle_match(&tmlr, cfg.as_ref()),
+                sync::CMD_SYNC => sync::handle_match(sub_matches, &tmlr),
+                daemon::CMD_DAEMON => daemon::handle_match(&tmlr),
+                autotrack::CMD_AUTOTRACK => {
+                    autotrack::handle_match(sub_matches, &tmlr, cfg.as_ref())
                 }
-                CMD_STOP => {
-                    log::info!("Not implemented");
-                    Ok(())
+                git::CMD_GIT => git::handle_match(sub_matches, &tmlr, cfg.as_ref()),
+                watch::CMD_WATCH => watch::handle_match(sub_matches, &tmlr, cfg.as_ref()),
+                pomodoro::CMD_POMODORO => pomodoro::handle_match(sub_matches, &tmlr, cfg.as_ref()),
+                audit::CMD_AUDIT => audit::handle_match(sub_matches, &tmlr, cfg.as_ref()),
+                balance::CMD_BALANCE => balance::handle_match(sub_matches, &tmlr, cfg.as_ref()),
+                timesheet::CMD_TIMESHEET => {
+                    timesheet::handle_match(sub_matches, &tmlr, cfg.as_ref())
                 }
                 _ => {
                     log::info!("Nothing found{}", matches.usage());
@@ -129,6 +232,81 @@ pub fn create_cli() -> Result<()> {
     }
 }
 
+/// Creates the app with all public commands, which is also the base for the completion scripts
+fn create_app<'a, 'b>() -> App<'a, 'b> {
+    App::new("tmlr")
+        .version(VERSION)
+        .about("Timular CLI Client")
+        .arg(
+            Arg::with_name(ARG_CONFIG)
+                .short("c")
+                .long(ARG_CONFIG)
+                .value_name("FILE")
+                .help("Sets a custom config file")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(ARG_API_KEY)
+                .long(ARG_API_KEY)
+                .help("Sets an API key")
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name(ARG_API_SECRET)
+                .long(ARG_API_SECRET)
+                .help("Sets an API secret")
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name(ARG_VERBOSE)
+                .long(ARG_VERBOSE)
+                .short("v")
+                .help("Sets the level of verbosity"),
+        )
+        .arg(
+            Arg::with_name(ARG_REFRESH)
+                .long(ARG_REFRESH)
+                .help("Ignores the cached responses and fetches them again"),
+        )
+        .subcommand(list::create_commands())
+        .subcommand(create::create_commands())
+        .subcommand(delete::create_commands())
+        .subcommand(edit::create_commands())
+        .subcommand(activity::create_commands())
+        .subcommand(time_entry::create_commands())
+        .subcommand(cli_config::create_commands())
+        .subcommand(start::create_commands())
+        .subcommand(stop::create_commands())
+        .subcommand(resume::create_commands())
+        .subcommand(switch::create_commands())
+        .subcommand(pomodoro::create_commands())
+        .subcommand(timesheet::create_commands())
+        .subcommand(watch::create_commands())
+        .subcommand(git::create_commands())
+        .subcommand(autotrack::create_commands())
+        .subcommand(balance::create_commands())
+        .subcommand(audit::create_commands())
+        .subcommand(status::create_commands())
+        .subcommand(sync::create_commands())
+        .subcommand(cli_cache::create_commands())
+        .subcommand(daemon::create_commands())
+        .subcommand(prompt::create_commands())
+        .subcommand(prompt::create_init_command())
+        .subcommand(completions::create_commands())
+}
+
+/// The API key of the account, which scopes the cached responses
+fn account<'a>(matches: &ArgMatches<'a>, cfg: Option<&Settings>) -> Option<String> {
+    create_auth_data(
+        cfg,
+        matches.value_of(ARG_API_KEY),
+        matches.value_of(ARG_API_SECRET),
+    )
+    .map(|a| a.credentials.api_key)
+}
+
 fn create_auth_data(
     cfg: Option<&Settings>,
     api_key: Option<&str>,
@@ -185,6 +363,7 @@ mod tests {
             Some(&Settings {
                 auth: None,
                 alias: None,
+                ..Settings::default()
             }),
             None,
         );
@@ -197,6 +376,7 @@ mod tests {
                     api_secret: None,
                 }),
                 alias: None,
+                ..Settings::default()
             }),
             None,
         );
@@ -209,6 +389,7 @@ mod tests {
                     api_secret: None,
                 }),
                 alias: None,
+                ..Settings::default()
             }),
             None,
         );
@@ -221,6 +402,7 @@ mod tests {
                     api_secret: Some("secret".to_owned()),
                 }),
                 alias: None,
+                ..Settings::default()
             }),
             None,
         );
@@ -233,6 +415,7 @@ mod tests {
                     api_secret: Some("secret".to_owned()),
                 }),
                 alias: None,
+                ..Settings::default()
             }),
             Some(TimeularAuth::new("key".to_owned(), "secret".to_owned())),
         );
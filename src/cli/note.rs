@@ -0,0 +1,202 @@
+use super::{resolver::Resolver, ARG_CREATE_TAGS};
+use crate::{
+    settings::Defaults,
+    timeular::{Note, NoteReference, TagResponse},
+    Result,
+};
+use clap::Arg;
+
+const TAG_PREFIX: char = '#';
+const MENTION_PREFIX: char = '@';
+
+/// A `#label` or `@label` token inside a note, `start` and `end` are byte offsets of the whole
+/// token including the prefix
+#[derive(Debug, PartialEq)]
+struct Token<'t> {
+    prefix: char,
+    label: &'t str,
+    start: usize,
+    end: usize,
+}
+
+pub fn create_tags_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name(ARG_CREATE_TAGS)
+        .help("Creates tags which are referenced in the note (#label) but don't exist yet")
+        .long(ARG_CREATE_TAGS)
+}
+
+/// Creates the note for a tracking or time entry. Inline `#tag` and `@mention` tokens are
+/// resolved to references, unknown ones are kept as plain text or, if `create_tags` is set,
+/// created as new tags. The default tags and mentions of the settings are appended to the text.
+pub fn create_note(
+    resolver: &Resolver,
+    text: Option<String>,
+    defaults: &Defaults,
+    create_tags: bool,
+) -> Result<Note> {
+    let note = match text {
+        Some(v) => parse_note(resolver, &v, defaults, create_tags)?,
+        None => Note::default(),
+    };
+
+    let is_new = |refs: &[NoteReference], t: &TagResponse| !refs.iter().any(|r| r.id == Some(t.id));
+    let tags = defaults
+        .tags
+        .iter()
+        .flatten()
+        .map(|v| resolver.tag(v))
+        .collect::<Result<Vec<TagResponse>>>()?
+        .into_iter()
+        .filter(|t| is_new(&note.tags, t))
+        .collect::<Vec<TagResponse>>();
+    let mentions = defaults
+        .mentions
+        .iter()
+        .flatten()
+        .map(|v| resolver.mention(v))
+        .collect::<Result<Vec<TagResponse>>>()?
+        .into_iter()
+        .filter(|t| is_new(&note.mentions, t))
+        .collect::<Vec<TagResponse>>();
+
+    Ok(note.merge(Note::with_references(None, &tags, &mentions)))
+}
+
+fn parse_note(
+    resolver: &Resolver,
+    text: &str,
+    defaults: &Defaults,
+    create_tags: bool,
+) -> Result<Note> {
+    let mut result = String::with_capacity(text.len());
+    let mut tags = Vec::new();
+    let mut mentions = Vec::new();
+    let mut last = 0;
+
+    for token in find_tokens(text) {
+        result.push_str(&text[last..token.start]);
+        last = token.end;
+
+        let reference = if token.prefix == TAG_PREFIX {
+            match resolver.find_tag(token.label)? {
+                Some(v) => Some(v),
+                None if create_tags => {
+                    log::info!("Creating tag \"{}\".", token.label);
+                    Some(resolver.create_tag(token.label, defaults.space.clone())?)
+                }
+                None => None,
+            }
+        } else {
+            resolver.find_mention(token.label)?
+        };
+
+        match reference {
+            Some(r) => {
+                let start = result.chars().count();
+                result.push(token.prefix);
+                result.push_str(&r.label);
+                let note_ref = NoteReference {
+                    id: Some(r.id),
+                    key: r.key,
+                    label: Some(r.label),
+                    indices: [start, result.chars().count()],
+                };
+                if token.prefix == TAG_PREFIX {
+                    tags.push(note_ref);
+                } else {
+                    mentions.push(note_ref);
+                }
+            }
+            None => {
+                log::info!(
+                    "Unknown reference \"{}\" is kept as text.",
+                    &text[token.start..token.end]
+                );
+                result.push_str(&text[token.start..token.end]);
+            }
+        }
+    }
+    result.push_str(&text[last..]);
+
+    Ok(Note {
+        text: if result.is_empty() {
+            None
+        } else {
+            Some(result)
+        },
+        tags,
+        mentions,
+    })
+}
+
+/// Finds all tokens which start with `#` or `@` at the beginning of a word, trailing dots are not
+/// part of the label so a note can end with a reference
+fn find_tokens(text: &str) -> Vec<Token<'_>> {
+    let is_label_char = |c: char| c.is_alphanumeric() || c == '-' || c == '_' || c == '.';
+    let mut tokens = Vec::new();
+    let mut previous: Option<char> = None;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((idx, c)) = chars.next() {
+        let at_word_start = !matches!(previous, Some(p) if !p.is_whitespace() && p != '(');
+        previous = Some(c);
+        if !at_word_start || (c != TAG_PREFIX && c != MENTION_PREFIX) {
+            continue;
+        }
+
+        let label_start = idx + c.len_utf8();
+        let mut label_end = label_start;
+        while let Some((i, l)) = chars.peek().copied() {
+            if !is_label_char(l) {
+                break;
+            }
+            label_end = i + l.len_utf8();
+            previous = Some(l);
+            chars.next();
+        }
+
+        let label = text[label_start..label_end].trim_end_matches('.');
+        if !label.is_empty() {
+            tokens.push(Token {
+                prefix: c,
+                label,
+                start: idx,
+                end: label_start + label.len(),
+            });
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_tokens() {
+        let text = "fixed #bug for @acme.";
+        let tokens = find_tokens(text);
+        assert_eq!(
+            tokens,
+            vec![
+                Token {
+                    prefix: '#',
+                    label: "bug",
+                    start: 6,
+                    end: 10
+                },
+                Token {
+                    prefix: '@',
+                    label: "acme",
+                    start: 15,
+                    end: 20
+                }
+            ]
+        );
+
+        assert!(find_tokens("mail to a@b.com, issue#1 or # and @").is_empty());
+        assert_eq!(find_tokens("(#über-ticket) #v1.2")[0].label, "über-ticket");
+        assert_eq!(find_tokens("(#über-ticket) #v1.2")[1].label, "v1.2");
+        assert_eq!(find_tokens("#a#b").len(), 1);
+    }
+}
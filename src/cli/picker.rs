@@ -0,0 +1,141 @@
+use crate::{
+    error::Error::TerminalError,
+    settings::Settings,
+    timeular::{ActivityResponse, Timeular},
+    util::color::ansi_foreground,
+    Result,
+};
+use atty::Stream;
+use chrono::{Duration, Utc};
+use dialoguer::{Confirm, FuzzySelect};
+use std::{cmp::Reverse, collections::HashMap};
+
+const RECENT_DAYS: i64 = 14;
+
+/// Lets the user pick an activity from a filterable list, recently used activities are shown
+/// first. Returns `None` if stdin is no terminal or if nothing was picked.
+pub fn pick_activity(tmlr: &Timeular, cfg: Option<&Settings>) -> Result<Option<ActivityResponse>> {
+    if !is_interactive() {
+        log::debug!("No terminal attached - skipping activity picker");
+        return Ok(None);
+    }
+
+    let list = tmlr.list_activities()?;
+    let mut activities = list.activities;
+    activities.extend(list.inactive_activities);
+
+    let now = Utc::now();
+    let mut entries = tmlr.list_time_entries(now - Duration::days(RECENT_DAYS), now)?;
+    entries.sort_by_key(|e| Reverse(e.duration.started_at.to_owned()));
+    let recent: Vec<String> = entries.into_iter().map(|e| e.activity_id).collect();
+    let activities = order_by_recent(activities, &recent);
+
+    let aliases = activity_aliases(cfg);
+    let items: Vec<String> = activities
+        .iter()
+        .map(|a| {
+            let name = format!("{} {}", ansi_foreground(&a.color, "●"), a.name);
+            match aliases.get(&a.id) {
+                Some(v) => format!("{} ({})", name, v.join(", ")),
+                None => name,
+            }
+        })
+        .collect();
+
+    let selection = FuzzySelect::new()
+        .with_prompt("Activity")
+        .items(&items)
+        .default(0)
+        .interact_opt()
+        .map_err(|e| TerminalError(e.to_string()))?;
+
+    Ok(selection.map(|idx| activities[idx].clone()))
+}
+
+/// Asks the user to confirm an action. Without a terminal nothing is confirmed.
+pub fn confirm(prompt: &str) -> Result<bool> {
+    if !is_interactive() {
+        log::debug!("No terminal attached - unable to confirm");
+        return Ok(false);
+    }
+
+    Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()
+        .map_err(|e| TerminalError(e.to_string()))
+}
+
+/// Whether the user can be asked, which needs a terminal for both input and output
+pub fn is_interactive() -> bool {
+    atty::is(Stream::Stdin) && atty::is(Stream::Stdout)
+}
+
+/// Maps activity ids to their sorted aliases
+pub fn activity_aliases(cfg: Option<&Settings>) -> HashMap<String, Vec<String>> {
+    let mut result: HashMap<String, Vec<String>> = HashMap::new();
+    if let Some(map) = cfg
+        .and_then(|c| c.alias.as_ref())
+        .and_then(|a| a.activity.as_ref())
+    {
+        for (alias, id) in map {
+            result
+                .entry(id.to_owned())
+                .or_default()
+                .push(alias.to_owned());
+        }
+    }
+    result.values_mut().for_each(|v| v.sort());
+    result
+}
+
+/// Moves the activities in the order of the recently used ids to the front, all other activities
+/// are sorted by name
+fn order_by_recent(
+    mut activities: Vec<ActivityResponse>,
+    recent: &[String],
+) -> Vec<ActivityResponse> {
+    activities.sort_by_key(|a| {
+        (
+            recent
+                .iter()
+                .position(|id| *id == a.id)
+                .unwrap_or(usize::MAX),
+            a.name.to_lowercase(),
+        )
+    });
+    activities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn activity(id: &str, name: &str) -> ActivityResponse {
+        ActivityResponse {
+            id: id.to_owned(),
+            name: name.to_owned(),
+            color: "#000000".to_owned(),
+            integration: "zei".to_owned(),
+            space_id: "1".to_owned(),
+            device_side: None,
+        }
+    }
+
+    #[test]
+    fn test_order_by_recent() {
+        let activities = vec![
+            activity("1", "b"),
+            activity("2", "a"),
+            activity("3", "d"),
+            activity("4", "C"),
+        ];
+        let recent = vec!["3".to_owned(), "1".to_owned(), "3".to_owned()];
+
+        let ids: Vec<String> = order_by_recent(activities, &recent)
+            .into_iter()
+            .map(|a| a.id)
+            .collect();
+        assert_eq!(ids, vec!["3", "1", "2", "4"]);
+    }
+}
@@ -0,0 +1,291 @@
+use super::{picker::pick_activity, resolver::Resolver, ARG_ACTIVITY};
+use crate::{
+    error::Error::{InvalidCommandError, TerminalError, TrackingRunningError},
+    settings::Settings,
+    timeular::Timeular,
+    util::time::{format_duration, parse_duration},
+    Result,
+};
+use atty::Stream;
+use chrono::{Duration, Utc};
+use clap::{App, Arg, ArgMatches};
+use std::{
+    io::{stdout, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Instant,
+};
+
+pub const CMD_POMODORO: &str = "pomodoro";
+
+const ARG_WORK: &str = "work";
+const ARG_BREAK: &str = "break";
+const ARG_CYCLES: &str = "cycles";
+const ARG_BREAK_ACTIVITY: &str = "break-activity";
+const ARG_SWITCH: &str = "switch";
+
+const DEFAULT_WORK: &str = "25m";
+const DEFAULT_BREAK: &str = "5m";
+const DEFAULT_CYCLES: u32 = 4;
+
+/// Interval in which the countdown is redrawn and Ctrl-C is checked
+const TICK: std::time::Duration = std::time::Duration::from_millis(200);
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_POMODORO)
+        .about("Tracks the activity in work blocks with breaks in between, Ctrl-C stops the tracking")
+        .arg(
+            Arg::with_name(ARG_ACTIVITY)
+                .help("Defines the activity id, name or alias. If no activity is passed the default activity of the settings will be taken or, on a terminal, an activity can be picked.")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name(ARG_WORK)
+                .help("Length of a work block, defaults to 25m")
+                .long(ARG_WORK)
+                .short("w")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(ARG_BREAK)
+                .help("Length of a break, defaults to 5m")
+                .long(ARG_BREAK)
+                .short("b")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(ARG_CYCLES)
+                .help("Number of work blocks, defaults to 4")
+                .long(ARG_CYCLES)
+                .short("c")
+                .takes_value(true)
+                .validator(|v| match v.parse::<u32>() {
+                    Ok(n) if n > 0 => Ok(()),
+                    _ => Err("cycles has to be a positive number".to_owned()),
+                }),
+        )
+        .arg(
+            Arg::with_name(ARG_BREAK_ACTIVITY)
+                .help("Tracks breaks with this activity instead of stopping the tracking")
+                .long(ARG_BREAK_ACTIVITY)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(ARG_SWITCH)
+                .help("Stops a running tracking instead of failing")
+                .long(ARG_SWITCH)
+                .short("s"),
+        )
+}
+
+/// Completed work blocks and the tracked time
+#[derive(Debug)]
+struct Summary {
+    completed: u32,
+    worked: Duration,
+    breaks: Duration,
+}
+
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let settings = cfg.and_then(|c| c.pomodoro.clone()).unwrap_or_default();
+    let work = parse_duration(
+        matches
+            .value_of(ARG_WORK)
+            .or(settings.work.as_deref())
+            .unwrap_or(DEFAULT_WORK),
+    )?;
+    let pause = parse_duration(
+        matches
+            .value_of(ARG_BREAK)
+            .or(settings.break_duration.as_deref())
+            .unwrap_or(DEFAULT_BREAK),
+    )?;
+    let cycles = matches
+        .value_of(ARG_CYCLES)
+        .and_then(|v| v.parse::<u32>().ok())
+        .or(settings.cycles)
+        .unwrap_or(DEFAULT_CYCLES);
+
+    let resolver = Resolver::new(tmlr, cfg);
+    let activity = match matches
+        .value_of(ARG_ACTIVITY)
+        .map(|v| v.to_owned())
+        .or_else(|| {
+            cfg.and_then(|c| c.defaults.as_ref())
+                .and_then(|d| d.activity.clone())
+        }) {
+        Some(v) => resolver.activity(&v)?,
+        None => match pick_activity(tmlr, cfg)? {
+            Some(v) => v,
+            None => {
+                println!("{}", matches.usage());
+                return Err(InvalidCommandError);
+            }
+        },
+    };
+    let break_activity = match matches
+        .value_of(ARG_BREAK_ACTIVITY)
+        .or(settings.break_activity.as_deref())
+    {
+        Some(v) => Some(resolver.activity(v)?),
+        None => None,
+    };
+
+    if !matches.is_present(ARG_SWITCH) {
+        if let Some(tracking) = tmlr.current_tracking()? {
+            let name = resolver
+                .activity(&tracking.activity_id)
+                .map(|a| a.name)
+                .unwrap_or(tracking.activity_id);
+            log::info!("Use --{} to stop it first.", ARG_SWITCH);
+            return Err(TrackingRunningError(name));
+        }
+    }
+
+    // Ctrl-C only sets the flag, the countdown ends and the tracking is stopped afterwards
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = interrupted.clone();
+    ctrlc::set_handler(move || flag.store(true, Ordering::SeqCst))
+        .map_err(|e| TerminalError(e.to_string()))?;
+
+    let mut summary = Summary {
+        completed: 0,
+        worked: Duration::zero(),
+        breaks: Duration::zero(),
+    };
+    let mut is_tracking = false;
+    let mut run_cycles = || -> Result<()> {
+        for cycle in 1..=cycles {
+            if tmlr.switch_tracking(&activity.id, None)?.is_some() && cycle == 1 {
+                log::info!("The running tracking was stopped.");
+            }
+            is_tracking = true;
+
+            let started = Utc::now();
+            let completed = countdown(
+                &format!("Work {}/{} on \"{}\"", cycle, cycles, activity.name),
+                work,
+                &interrupted,
+            );
+            summary.worked = summary.worked + (Utc::now() - started);
+            if !completed {
+                break;
+            }
+            summary.completed += 1;
+            if cycle == cycles {
+                break;
+            }
+
+            match &break_activity {
+                Some(b) => {
+                    tmlr.switch_tracking(&b.id, None)?;
+                }
+                None => {
+                    tmlr.stop_tracking()?;
+                    is_tracking = false;
+                }
+            }
+            let started = Utc::now();
+            let completed = countdown("Break", pause, &interrupted);
+            summary.breaks = summary.breaks + (Utc::now() - started);
+            if !completed {
+                break;
+            }
+        }
+        Ok(())
+    };
+
+    // The tracking is stopped on errors as well, the pomodoro mustn't keep running unattended
+    let result = run_cycles();
+    if is_tracking {
+        match tmlr.stop_tracking() {
+            Ok(_) => (),
+            Err(e) if result.is_ok() => return Err(e),
+            Err(e) => log::error!("Unable to stop the tracking: {}", e),
+        }
+    }
+    result?;
+    if interrupted.load(Ordering::SeqCst) {
+        log::info!("Interrupted, the tracking was stopped.");
+    }
+    println!(
+        "Completed {} of {} cycles, {} worked on \"{}\", {} of breaks.",
+        summary.completed,
+        cycles,
+        format_duration(summary.worked),
+        activity.name,
+        format_duration(summary.breaks)
+    );
+    Ok(())
+}
+
+/// Counts down the duration on the terminal, returns `false` if it was interrupted
+fn countdown(label: &str, duration: Duration, interrupted: &AtomicBool) -> bool {
+    log::info!("{} for {}.", label, format_duration(duration));
+    let end = Instant::now() + duration.to_std().unwrap_or_default();
+    let is_terminal = atty::is(Stream::Stdout);
+
+    loop {
+        if interrupted.load(Ordering::SeqCst) {
+            if is_terminal {
+                println!();
+            }
+            return false;
+        }
+
+        let now = Instant::now();
+        if now >= end {
+            if is_terminal {
+                // Clears the countdown and rings the bell
+                print!("\r\x1b[2K\x07");
+                let _ = stdout().flush();
+            }
+            return true;
+        }
+
+        if is_terminal {
+            print!("\r\x1b[2K{} {}", label, format_remaining(end - now));
+            let _ = stdout().flush();
+        }
+        thread::sleep(TICK);
+    }
+}
+
+/// Formats the remaining time as `mm:ss`, rounded up to full seconds
+fn format_remaining(remaining: std::time::Duration) -> String {
+    let secs = remaining.as_secs() + if remaining.subsec_nanos() > 0 { 1 } else { 0 };
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_remaining() {
+        let secs = std::time::Duration::from_secs;
+        assert_eq!(format_remaining(secs(25 * 60)), "25:00");
+        assert_eq!(format_remaining(secs(61)), "01:01");
+        assert_eq!(
+            format_remaining(std::time::Duration::from_millis(59_001)),
+            "01:00"
+        );
+        assert_eq!(format_remaining(secs(0)), "00:00");
+    }
+
+    #[test]
+    fn test_countdown() {
+        let interrupted = AtomicBool::new(false);
+        assert!(countdown("Work", Duration::zero(), &interrupted));
+
+        interrupted.store(true, Ordering::SeqCst);
+        assert!(!countdown("Work", Duration::minutes(25), &interrupted));
+    }
+}
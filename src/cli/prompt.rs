@@ -0,0 +1,199 @@
+use super::{
+    cache as cli_cache,
+    completions::{SHELL_BASH, SHELL_FISH, SHELL_ZSH},
+    status::Fields,
+};
+use crate::{
+    cache::{Cache, KEY_ACTIVITIES, KEY_TRACKING, TRACKING_TTL_SECS},
+    error::Error::InvalidCommandError,
+    settings::Settings,
+    timeular::{ActivityListResponse, CurrentTrackingResponse},
+    util::color::parse_hex,
+    Result,
+};
+use chrono::{Duration, Utc};
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+pub const CMD_PROMPT: &str = "prompt";
+pub const CMD_INIT: &str = "init";
+
+const ARG_SHELL: &str = "shell";
+
+const ANSI_RESET: &str = "\x1b[0m";
+/// A tracking which wasn't fetched for a few TTLs may have been stopped elsewhere meanwhile
+const MAX_TRACKING_AGE_SECS: i64 = 4 * TRACKING_TTL_SECS;
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(CMD_PROMPT)
+        .about("Prints the current tracking for the shell prompt, only the local cache is read so it has to be enabled")
+        .arg(
+            Arg::with_name(ARG_SHELL)
+                .help("Marks the escape codes as non-printing for the prompt of the shell")
+                .long(ARG_SHELL)
+                .possible_values(&[SHELL_BASH, SHELL_ZSH, SHELL_FISH])
+                .takes_value(true),
+        )
+}
+
+pub fn create_init_command<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(CMD_INIT)
+        .about("Prints the hook which adds the current tracking to the prompt, e.g. `eval \"$(tmlr init bash)\"`")
+        .arg(
+            Arg::with_name(ARG_SHELL)
+                .help("Defines the shell")
+                .possible_values(&[SHELL_BASH, SHELL_ZSH, SHELL_FISH])
+                .required(true),
+        )
+}
+
+/// Prints nothing if no recent tracking is known, the prompt must never wait for the API
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    cfg: Option<&Settings>,
+    api_key: Option<&str>,
+) -> Result<()> {
+    if !cli_cache::is_enabled(cfg) {
+        log::debug!("The cache is disabled, no tracking is known for the prompt.");
+        return Ok(());
+    }
+    let cache = match Cache::open_default(Duration::zero(), Duration::zero(), false) {
+        Ok(v) => v.for_account(api_key),
+        Err(e) => {
+            log::debug!("Unable to open the cache for the prompt: {}", e);
+            return Ok(());
+        }
+    };
+
+    let tracking = cache
+        .get_younger::<Option<CurrentTrackingResponse>>(
+            KEY_TRACKING,
+            Duration::seconds(MAX_TRACKING_AGE_SECS),
+        )
+        .flatten();
+    if let Some(t) = tracking {
+        let activities = cache.get_stale::<ActivityListResponse>(KEY_ACTIVITIES);
+        let fields = Fields::new(&t, activities.as_ref(), cfg, Utc::now());
+        println!(
+            "{}",
+            segment(
+                &fields,
+                Utc::now() - t.started_at,
+                matches.value_of(ARG_SHELL)
+            )
+        );
+    }
+    Ok(())
+}
+
+pub fn handle_init<'a>(matches: &ArgMatches<'a>) -> Result<()> {
+    let hook = match matches.value_of(ARG_SHELL).unwrap_or_default() {
+        SHELL_BASH => BASH_HOOK,
+        SHELL_ZSH => ZSH_HOOK,
+        SHELL_FISH => FISH_HOOK,
+        _ => {
+            println!("{}", matches.usage());
+            return Err(InvalidCommandError);
+        }
+    };
+    print!("{}", hook);
+    Ok(())
+}
+
+/// Formats the alias (or name) in the color of the activity followed by the elapsed time as
+/// `h:mm`
+fn segment(fields: &Fields, elapsed: Duration, shell: Option<&str>) -> String {
+    let name = if fields.alias.is_empty() {
+        &fields.activity
+    } else {
+        &fields.alias
+    };
+    let minutes = elapsed.num_minutes().max(0);
+    let elapsed = format!("{}:{:02}", minutes / 60, minutes % 60);
+
+    // Escape codes have to be marked, otherwise the shell miscalculates the prompt width
+    let (open, close) = match shell {
+        Some(SHELL_BASH) => ("\x01", "\x02"),
+        Some(SHELL_ZSH) => ("%{", "%}"),
+        _ => ("", ""),
+    };
+    let (name, elapsed) = match shell {
+        Some(SHELL_ZSH) => (name.replace('%', "%%"), elapsed),
+        _ => (name.to_owned(), elapsed),
+    };
+
+    match parse_hex(&fields.color) {
+        Some((r, g, b)) => format!(
+            "{open}\x1b[38;2;{r};{g};{b}m{close}{name}{open}{reset}{close} {elapsed}",
+            open = open,
+            close = close,
+            r = r,
+            g = g,
+            b = b,
+            name = name,
+            reset = ANSI_RESET,
+            elapsed = elapsed
+        ),
+        None => format!("{} {}", name, elapsed),
+    }
+}
+
+const BASH_HOOK: &str = r#"__tmlr_prompt() {
+    local segment
+    segment="$(tmlr prompt --shell bash 2>/dev/null)"
+    TMLR_PROMPT="${segment:+$segment }"
+}
+if [[ ";${PROMPT_COMMAND};" != *";__tmlr_prompt;"* ]]; then
+    PROMPT_COMMAND="__tmlr_prompt${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+    PS1='${TMLR_PROMPT}'"${PS1}"
+fi
+"#;
+
+const ZSH_HOOK: &str = r#"autoload -Uz add-zsh-hook
+setopt prompt_subst
+__tmlr_prompt() {
+    local segment
+    segment="$(tmlr prompt --shell zsh 2>/dev/null)"
+    TMLR_PROMPT="${segment:+$segment }"
+}
+if (( ! ${precmd_functions[(I)__tmlr_prompt]} )); then
+    add-zsh-hook precmd __tmlr_prompt
+    PROMPT='${TMLR_PROMPT}'"${PROMPT}"
+fi
+"#;
+
+const FISH_HOOK: &str = r#"if not functions -q __tmlr_original_prompt
+    functions -c fish_prompt __tmlr_original_prompt
+    function fish_prompt
+        set -l segment (tmlr prompt --shell fish 2>/dev/null)
+        test -n "$segment"; and printf '%s ' $segment
+        __tmlr_original_prompt
+    end
+end
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment() {
+        let mut fields = Fields {
+            activity: "Development".to_owned(),
+            color: "#a1b2c3".to_owned(),
+            ..Fields::default()
+        };
+        let elapsed = Duration::minutes(65);
+        assert_eq!(
+            segment(&fields, elapsed, None),
+            "\x1b[38;2;161;178;195mDevelopment\x1b[0m 1:05"
+        );
+        assert_eq!(
+            segment(&fields, elapsed, Some(SHELL_BASH)),
+            "\x01\x1b[38;2;161;178;195m\x02Development\x01\x1b[0m\x02 1:05"
+        );
+
+        fields.alias = "dev%".to_owned();
+        fields.color = String::new();
+        assert_eq!(segment(&fields, elapsed, Some(SHELL_ZSH)), "dev%% 1:05");
+    }
+}
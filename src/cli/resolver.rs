@@ -0,0 +1,327 @@
+use crate::{
+    error::Error::{AmbiguousReferenceError, ReferenceNotFoundError},
+    settings::Settings,
+    timeular::{ActivityResponse, TagResponse, TagsAndMentionsResponse, Timeular},
+    Result,
+};
+use std::cell::RefCell;
+
+const KIND_ACTIVITY: &str = "activity";
+const KIND_TAG: &str = "tag";
+const KIND_MENTION: &str = "mention";
+
+/// Resolves user input to activities, tags and mentions.
+///
+/// The input is checked in the following order:
+/// 1. An alias of the settings
+/// 2. The exact id
+/// 3. The exact name (or label), first case sensitive then case insensitive
+/// 4. A case insensitive prefix of the name
+/// 5. A fuzzy match of the name, i.e. all characters appear in the same order
+///
+/// The lists are only fetched once per resolver and only when they are needed.
+pub struct Resolver<'a> {
+    tmlr: &'a Timeular<'a>,
+    cfg: Option<&'a Settings>,
+    activities: RefCell<Option<Vec<ActivityResponse>>>,
+    archived_activities: RefCell<Option<Vec<ActivityResponse>>>,
+    tnm: RefCell<Option<TagsAndMentionsResponse>>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(tmlr: &'a Timeular<'a>, cfg: Option<&'a Settings>) -> Self {
+        Resolver {
+            tmlr,
+            cfg,
+            activities: RefCell::new(None),
+            archived_activities: RefCell::new(None),
+            tnm: RefCell::new(None),
+        }
+    }
+
+    pub fn activity(&self, input: &str) -> Result<ActivityResponse> {
+        self.fetch_activities()?;
+        let activities = self.activities.borrow();
+        self.resolve_activity(input, activities.as_deref().unwrap_or_default())
+    }
+
+    /// Resolves an archived activity, which is not considered by [`Resolver::activity`]
+    pub fn archived_activity(&self, input: &str) -> Result<ActivityResponse> {
+        self.fetch_activities()?;
+        let activities = self.archived_activities.borrow();
+        self.resolve_activity(input, activities.as_deref().unwrap_or_default())
+    }
+
+    fn resolve_activity(
+        &self,
+        input: &str,
+        activities: &[ActivityResponse],
+    ) -> Result<ActivityResponse> {
+        resolve(
+            KIND_ACTIVITY,
+            input,
+            self.cfg.and_then(|c| c.resolve_activity_alias(input)),
+            activities,
+            |a| a.id.to_owned(),
+            |a| &a.name,
+        )
+        .cloned()
+    }
+
+    pub fn tag(&self, input: &str) -> Result<TagResponse> {
+        self.fetch_tags_and_mentions()?;
+        let tnm = self.tnm.borrow();
+        resolve(
+            KIND_TAG,
+            input,
+            self.cfg.and_then(|c| c.resolve_tag_alias(input)),
+            tnm.as_ref().map(|v| v.tags.as_slice()).unwrap_or_default(),
+            |t| t.id.to_string(),
+            |t| &t.label,
+        )
+        .cloned()
+    }
+
+    pub fn mention(&self, input: &str) -> Result<TagResponse> {
+        self.fetch_tags_and_mentions()?;
+        let tnm = self.tnm.borrow();
+        resolve(
+            KIND_MENTION,
+            input,
+            self.cfg.and_then(|c| c.resolve_mention_alias(input)),
+            tnm.as_ref()
+                .map(|v| v.mentions.as_slice())
+                .unwrap_or_default(),
+            |t| t.id.to_string(),
+            |t| &t.label,
+        )
+        .cloned()
+    }
+
+    /// Finds a tag referenced inside a note by alias, key or label. Contrary to
+    /// [`Resolver::tag`] only exact (case insensitive) matches are considered.
+    pub fn find_tag(&self, input: &str) -> Result<Option<TagResponse>> {
+        self.fetch_tags_and_mentions()?;
+        let tnm = self.tnm.borrow();
+        find_exact(
+            KIND_TAG,
+            input,
+            self.cfg.and_then(|c| c.resolve_tag_alias(input)),
+            tnm.as_ref().map(|v| v.tags.as_slice()).unwrap_or_default(),
+        )
+    }
+
+    /// Finds a mention referenced inside a note, see [`Resolver::find_tag`]
+    pub fn find_mention(&self, input: &str) -> Result<Option<TagResponse>> {
+        self.fetch_tags_and_mentions()?;
+        let tnm = self.tnm.borrow();
+        find_exact(
+            KIND_MENTION,
+            input,
+            self.cfg.and_then(|c| c.resolve_mention_alias(input)),
+            tnm.as_ref()
+                .map(|v| v.mentions.as_slice())
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Creates a new tag and adds it to the already fetched tags
+    pub fn create_tag(&self, label: &str, space_id: Option<String>) -> Result<TagResponse> {
+        self.fetch_tags_and_mentions()?;
+        let tag = self.tmlr.create_tag(label.to_owned(), None, space_id)?;
+        if let Some(tnm) = self.tnm.borrow_mut().as_mut() {
+            tnm.tags.push(tag.clone());
+        }
+        Ok(tag)
+    }
+
+    fn fetch_activities(&self) -> Result<()> {
+        if self.activities.borrow().is_none() {
+            let list = self.tmlr.list_activities()?;
+            let mut activities = list.activities;
+            activities.extend(list.inactive_activities);
+            self.activities.replace(Some(activities));
+            self.archived_activities
+                .replace(Some(list.archived_activities));
+        }
+        Ok(())
+    }
+
+    fn fetch_tags_and_mentions(&self) -> Result<()> {
+        if self.tnm.borrow().is_none() {
+            let tnm = self.tmlr.list_tags_and_mentions()?;
+            self.tnm.replace(Some(tnm));
+        }
+        Ok(())
+    }
+}
+
+fn resolve<'e, T>(
+    kind: &str,
+    input: &str,
+    alias_id: Option<String>,
+    entries: &'e [T],
+    id: impl Fn(&T) -> String,
+    name: impl Fn(&T) -> &str,
+) -> Result<&'e T> {
+    let not_found = || ReferenceNotFoundError(kind.to_owned(), input.to_owned());
+
+    if let Some(alias_id) = alias_id {
+        log::debug!("Resolved {} alias \"{}\" to {}", kind, input, alias_id);
+        return entries
+            .iter()
+            .find(|e| id(e) == alias_id)
+            .ok_or_else(not_found);
+    }
+
+    if let Some(e) = entries.iter().find(|e| id(e) == input) {
+        return Ok(e);
+    }
+    if let Some(e) = entries.iter().find(|e| name(e) == input) {
+        return Ok(e);
+    }
+
+    let lower_input = input.to_lowercase();
+    let matchers: [&dyn Fn(&str) -> bool; 3] = [
+        &|n| n == lower_input,
+        &|n| n.starts_with(&lower_input),
+        &|n| is_fuzzy_match(&lower_input, n),
+    ];
+
+    for matcher in matchers.iter() {
+        let candidates: Vec<&T> = entries
+            .iter()
+            .filter(|e| matcher(&name(e).to_lowercase()))
+            .collect();
+
+        match candidates.len() {
+            0 => continue,
+            1 => return Ok(candidates[0]),
+            _ => {
+                return Err(AmbiguousReferenceError(
+                    kind.to_owned(),
+                    input.to_owned(),
+                    candidates
+                        .iter()
+                        .map(|e| format!("\"{}\" ({})", name(e), id(e)))
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                ))
+            }
+        }
+    }
+
+    Err(not_found())
+}
+
+fn find_exact(
+    kind: &str,
+    input: &str,
+    alias_id: Option<String>,
+    entries: &[TagResponse],
+) -> Result<Option<TagResponse>> {
+    if let Some(alias_id) = alias_id {
+        return Ok(entries
+            .iter()
+            .find(|e| e.id.to_string() == alias_id)
+            .cloned());
+    }
+    if let Some(e) = entries.iter().find(|e| e.label == input || e.key == input) {
+        return Ok(Some(e.clone()));
+    }
+
+    let lower_input = input.to_lowercase();
+    let candidates: Vec<&TagResponse> = entries
+        .iter()
+        .filter(|e| e.label.to_lowercase() == lower_input || e.key.to_lowercase() == lower_input)
+        .collect();
+    match candidates.len() {
+        0 => Ok(None),
+        1 => Ok(Some(candidates[0].clone())),
+        _ => Err(AmbiguousReferenceError(
+            kind.to_owned(),
+            input.to_owned(),
+            candidates
+                .iter()
+                .map(|e| format!("\"{}\" ({})", e.label, e.id))
+                .collect::<Vec<String>>()
+                .join(", "),
+        )),
+    }
+}
+
+/// Checks if all characters of the pattern appear in the same order in the value
+fn is_fuzzy_match(pattern: &str, value: &str) -> bool {
+    let mut chars = value.chars();
+    pattern.chars().all(|p| chars.any(|c| c == p))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<(String, String)> {
+        vec![
+            ("1".to_owned(), "Development".to_owned()),
+            ("2".to_owned(), "Design".to_owned()),
+            ("3".to_owned(), "Meetings".to_owned()),
+            ("4".to_owned(), "meetings".to_owned()),
+        ]
+    }
+
+    fn do_resolve<'e>(
+        entries: &'e [(String, String)],
+        input: &str,
+        alias_id: Option<&str>,
+    ) -> Result<&'e (String, String)> {
+        resolve(
+            "activity",
+            input,
+            alias_id.map(|v| v.to_owned()),
+            entries,
+            |e| e.0.to_owned(),
+            |e| &e.1,
+        )
+    }
+
+    #[test]
+    fn test_is_fuzzy_match() {
+        assert!(is_fuzzy_match("dvl", "development"));
+        assert!(is_fuzzy_match("", "development"));
+        assert!(!is_fuzzy_match("dvx", "development"));
+        assert!(!is_fuzzy_match("ld", "development"));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let entries = entries();
+
+        // Alias, id and exact names
+        assert_eq!(do_resolve(&entries, "dev", Some("2")).unwrap().0, "2");
+        assert!(matches!(
+            do_resolve(&entries, "dev", Some("9")),
+            Err(ReferenceNotFoundError(_, _))
+        ));
+        assert_eq!(do_resolve(&entries, "3", None).unwrap().0, "3");
+        assert_eq!(do_resolve(&entries, "meetings", None).unwrap().0, "4");
+        assert_eq!(do_resolve(&entries, "DESIGN", None).unwrap().0, "2");
+
+        // Prefix and fuzzy
+        assert_eq!(do_resolve(&entries, "dev", None).unwrap().0, "1");
+        assert_eq!(do_resolve(&entries, "dsg", None).unwrap().0, "2");
+
+        // Ambiguous and unknown
+        assert!(matches!(
+            do_resolve(&entries, "de", None),
+            Err(AmbiguousReferenceError(_, _, ref c)) if c == "\"Development\" (1), \"Design\" (2)"
+        ));
+        assert!(matches!(
+            do_resolve(&entries, "MEETINGS", None),
+            Err(AmbiguousReferenceError(_, _, _))
+        ));
+        assert!(matches!(
+            do_resolve(&entries, "xyz", None),
+            Err(ReferenceNotFoundError(_, _))
+        ));
+    }
+}
@@ -0,0 +1,141 @@
+use super::resolver::Resolver;
+use crate::{
+    error::Error::{ReferenceNotFoundError, TrackingRunningError},
+    settings::Settings,
+    timeular::{TimeEntryResponse, Timeular},
+    Result,
+};
+use chrono::{Duration, Utc};
+use clap::{App, Arg, ArgMatches};
+use std::cmp::Reverse;
+
+pub const CMD_CONTINUE: &str = "continue";
+
+const ARG_N: &str = "n";
+const ARG_SWITCH: &str = "switch";
+const RECENT_DAYS: i64 = 30;
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_CONTINUE)
+        .about("Starts tracking the activity and note of the last time entry again")
+        .arg(
+            Arg::with_name(ARG_N)
+                .help("Takes the n-th last distinct time entry instead of the last one")
+                .required(false)
+                .validator(|v| match v.parse::<usize>() {
+                    Ok(n) if n > 0 => Ok(()),
+                    _ => Err("n has to be a positive number".to_owned()),
+                }),
+        )
+        .arg(
+            Arg::with_name(ARG_SWITCH)
+                .help("Stops a running tracking instead of failing")
+                .long(ARG_SWITCH)
+                .short("s"),
+        )
+}
+
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let n = matches
+        .value_of(ARG_N)
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(1);
+
+    let now = Utc::now();
+    let entries = tmlr.fetch_time_entries(now - Duration::days(RECENT_DAYS), now)?;
+    let entry = nth_distinct(entries, n)
+        .ok_or_else(|| ReferenceNotFoundError("time entry".to_owned(), n.to_string()))?;
+
+    let resolver = Resolver::new(tmlr, cfg);
+    let activity_name = |id: &str| {
+        resolver
+            .activity(id)
+            .map(|a| a.name)
+            .unwrap_or_else(|_| id.to_owned())
+    };
+
+    if matches.is_present(ARG_SWITCH) {
+        if let Some(stopped) = tmlr.switch_tracking(&entry.activity_id, entry.note.clone())? {
+            log::info!(
+                "Tracking of activity \"{}\" stopped.",
+                activity_name(&stopped.activity_id)
+            );
+        }
+    } else {
+        if let Some(tracking) = tmlr.current_tracking()? {
+            log::info!("Use --{} to stop it first.", ARG_SWITCH);
+            return Err(TrackingRunningError(activity_name(&tracking.activity_id)));
+        }
+        tmlr.start_tracking(&entry.activity_id, entry.note.clone())?;
+    }
+    log::info!(
+        "Tracking of activity \"{}\" continued.",
+        activity_name(&entry.activity_id)
+    );
+    Ok(())
+}
+
+/// Returns the n-th last time entry (starting with 1), entries with the same activity and note
+/// as a more recent one are skipped
+fn nth_distinct(mut entries: Vec<TimeEntryResponse>, n: usize) -> Option<TimeEntryResponse> {
+    entries.sort_by_key(|e| Reverse(e.duration.started_at));
+
+    let key = |e: &TimeEntryResponse| {
+        (
+            e.activity_id.to_owned(),
+            e.note.as_ref().and_then(|n| n.text.to_owned()),
+        )
+    };
+    let mut seen = Vec::new();
+    entries
+        .into_iter()
+        .filter(|e| {
+            let k = key(e);
+            if seen.contains(&k) {
+                false
+            } else {
+                seen.push(k);
+                true
+            }
+        })
+        .nth(n - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeular::Note;
+    use chrono::{DateTime, TimeZone};
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.ymd(2021, 6, 1).and_hms(hour, 0, 0)
+    }
+
+    fn note(text: &str) -> Note {
+        Note {
+            text: Some(text.to_owned()),
+            ..Note::default()
+        }
+    }
+
+    #[test]
+    fn test_nth_distinct() {
+        let entries = vec![
+            TimeEntryResponse::new("1", "a", at(8), at(9)),
+            TimeEntryResponse::new("2", "b", at(9), at(10)).with_note(note("review")),
+            TimeEntryResponse::new("3", "a", at(10), at(11)),
+            TimeEntryResponse::new("4", "b", at(11), at(12)).with_note(note("review")),
+            TimeEntryResponse::new("5", "b", at(12), at(13)).with_note(note("planning")),
+        ];
+
+        assert_eq!(nth_distinct(entries.clone(), 1).unwrap().id, "5");
+        assert_eq!(nth_distinct(entries.clone(), 2).unwrap().id, "4");
+        assert_eq!(nth_distinct(entries.clone(), 3).unwrap().id, "3");
+        assert!(nth_distinct(entries, 4).is_none());
+        assert!(nth_distinct(Vec::new(), 1).is_none());
+    }
+}
@@ -0,0 +1,106 @@
+use super::{
+    note::{create_note, create_tags_arg},
+    picker::pick_activity,
+    resolver::Resolver,
+    sync::record_offline,
+    ARG_ACTIVITY, ARG_CREATE_TAGS, ARG_NOTE, CMD_TRACKING,
+};
+use crate::{
+    error::Error::InvalidCommandError, journal::Operation, settings::Settings, timeular::Timeular,
+    Result,
+};
+use chrono::Utc;
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+pub const CMD_START: &str = "start";
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_START)
+        .about("Starts a Resource. Without a subcommand a tracking will be started.")
+        .arg(
+            Arg::with_name(ARG_NOTE)
+                .help("Defines a note for the tracking, #tag and @mention are referenced")
+                .long(ARG_NOTE)
+                .short("n")
+                .takes_value(true)
+                .required(false)
+        )
+        .arg(create_tags_arg())
+        .subcommand(
+            SubCommand::with_name(CMD_TRACKING)
+                .about("Starts tracking an activity")
+                .arg(
+                    Arg::with_name(ARG_ACTIVITY)
+                        .help("Defines the activity id, name or alias. If no activity is passed the default activity of the settings will be taken or, on a terminal, an activity can be picked.")
+                        .required(false)
+                )
+                .arg(
+                    Arg::with_name(ARG_NOTE)
+                        .help("Defines a note for the tracking, #tag and @mention are referenced")
+                        .long(ARG_NOTE)
+                        .short("n")
+                        .takes_value(true)
+                        .required(false)
+                )
+                .arg(create_tags_arg())
+        )
+}
+
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    match matches.subcommand() {
+        (CMD_TRACKING, Some(sub_matches)) => start_tracking(tmlr, sub_matches, cfg),
+        ("", None) => start_tracking(tmlr, matches, cfg),
+        _ => {
+            println!("{}", matches.usage());
+            Err(InvalidCommandError)
+        }
+    }
+}
+
+fn start_tracking<'a>(
+    tmlr: &Timeular,
+    matches: &ArgMatches<'a>,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let defaults = cfg.and_then(|c| c.defaults.clone()).unwrap_or_default();
+
+    let activity = matches
+        .value_of(ARG_ACTIVITY)
+        .map(|v| v.to_string())
+        .or_else(|| defaults.activity.clone());
+
+    let resolver = Resolver::new(tmlr, cfg);
+    let activity = match activity {
+        Some(v) => resolver.activity(&v)?,
+        None => match pick_activity(tmlr, cfg)? {
+            Some(v) => v,
+            None => {
+                println!("{}", matches.usage());
+                return Err(InvalidCommandError);
+            }
+        },
+    };
+    let note = create_note(
+        &resolver,
+        matches.value_of(ARG_NOTE).map(|v| v.to_string()),
+        &defaults,
+        matches.is_present(ARG_CREATE_TAGS),
+    )?;
+
+    let now = Utc::now();
+    let result = tmlr.start_tracking_at(&activity.id, now, Some(note.clone()));
+    let recorded = || Operation::StartTracking {
+        activity_id: activity.id.to_owned(),
+        activity_name: activity.name.to_owned(),
+        started_at: now,
+        note: Some(note).filter(|n| !n.is_empty()),
+    };
+    if record_offline(result, recorded)?.is_some() {
+        log::info!("Tracking of activity \"{}\" started.", activity.name);
+    }
+    Ok(())
+}
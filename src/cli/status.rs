@@ -0,0 +1,340 @@
+use super::{cache as cli_cache, resolver::Resolver, sync::CMD_SYNC};
+use crate::{
+    cache::{Cache, KEY_ACTIVITIES, KEY_TRACKING},
+    daemon::DaemonClient,
+    error::Error::AuthenticationInformationMissingError,
+    journal::{Journal, Operation},
+    settings::Settings,
+    timeular::{ActivityListResponse, CurrentTrackingResponse, Timeular, TimeularAuth},
+    util::{color::ansi_foreground, time::format_duration},
+    Result,
+};
+use chrono::{DateTime, Local, Utc};
+use clap::{App, Arg, ArgMatches};
+use serde_json::json;
+
+pub const CMD_STATUS: &str = "status";
+
+const ARG_FORMAT: &str = "format";
+
+const FORMAT_WAYBAR: &str = "waybar";
+const FORMAT_I3BLOCKS: &str = "i3blocks";
+const FORMAT_TMUX: &str = "tmux";
+
+/// Full text, short text and color, one per line as expected by i3blocks
+const TEMPLATE_I3BLOCKS: &str = "{activity} {elapsed}\n{elapsed}\n{color}";
+const TEMPLATE_TMUX: &str = "#[fg={color}]●#[default] {activity} {elapsed}";
+const TEMPLATE_WAYBAR_TEXT: &str = "{activity} {elapsed}";
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_STATUS)
+        .about("Shows the current tracking and the operations which are not synced yet")
+        .arg(
+            Arg::with_name(ARG_FORMAT)
+                .help(
+                    "Prints only the current tracking for status bars, either with the presets \
+                    waybar, i3blocks and tmux or with a template with the placeholders \
+                    {activity}, {alias}, {color}, {elapsed}, {started} and {note}",
+                )
+                .long(ARG_FORMAT)
+                .short("f")
+                .takes_value(true),
+        )
+}
+
+/// Whether the status is printed for a status bar, which is handled without the regular login
+pub fn is_formatted<'a>(matches: &ArgMatches<'a>) -> bool {
+    matches.is_present(ARG_FORMAT)
+}
+
+/// Prints the current tracking with the format. The tracking is cached for a short time and
+/// fetched from the daemon if one runs, so frequent polling needs neither API calls nor logins.
+pub fn handle_format<'a>(
+    matches: &ArgMatches<'a>,
+    cfg: Option<&Settings>,
+    auth: Option<TimeularAuth>,
+) -> Result<()> {
+    let cache = if cli_cache::is_enabled(cfg) {
+        let api_key = auth.as_ref().map(|a| a.credentials.api_key.as_str());
+        cli_cache::open_cache(cfg, api_key, false)
+            .map_err(|e| log::debug!("Continuing without cache: {}", e))
+            .ok()
+    } else {
+        None
+    };
+
+    let cached = cache
+        .as_ref()
+        .and_then(|c| c.get::<Option<CurrentTrackingResponse>>(KEY_TRACKING));
+    let tracking = match cached {
+        Some(v) => v,
+        None => {
+            let tracking = fetch_tracking(auth, cache.as_ref())?;
+            if let Some(c) = &cache {
+                c.put(KEY_TRACKING, &tracking);
+            }
+            tracking
+        }
+    };
+
+    let activities = cache
+        .as_ref()
+        .and_then(|c| c.get_stale::<ActivityListResponse>(KEY_ACTIVITIES));
+    let fields = tracking.map(|t| Fields::new(&t, activities.as_ref(), cfg, Utc::now()));
+
+    let format = matches.value_of(ARG_FORMAT).unwrap_or_default();
+    println!("{}", format_status(format, fields.as_ref()));
+    Ok(())
+}
+
+/// Fetches the current tracking, also caches the activities for their names if they are missing
+fn fetch_tracking(
+    auth: Option<TimeularAuth>,
+    cache: Option<&Cache>,
+) -> Result<Option<CurrentTrackingResponse>> {
+    let has_activities = cache
+        .and_then(|c| c.get_stale::<ActivityListResponse>(KEY_ACTIVITIES))
+        .is_some();
+
+    // A daemon of another account must not answer for this one
+    let api_key = auth.as_ref().map(|a| a.credentials.api_key.as_str());
+    let daemon = DaemonClient::connect()
+        .ok()
+        .filter(|d| matches!(api_key, Some(k) if d.token(k).is_ok()));
+    if let Some(daemon) = daemon {
+        match daemon.current_tracking() {
+            Ok(tracking) => {
+                if let (Some(c), false) = (cache, has_activities) {
+                    if let Ok(list) = daemon.list_activities() {
+                        c.put(KEY_ACTIVITIES, &list);
+                    }
+                }
+                return Ok(tracking);
+            }
+            Err(e) => log::debug!("Daemon failed, using the API: {}", e),
+        }
+    }
+
+    let tmlr = Timeular::new(auth.ok_or(AuthenticationInformationMissingError)?)?;
+    let tracking = tmlr.current_tracking()?;
+    if let (Some(c), false) = (cache, has_activities) {
+        if let Ok(list) = tmlr.list_activities() {
+            c.put(KEY_ACTIVITIES, &list);
+        }
+    }
+    Ok(tracking)
+}
+
+/// The values of the placeholders
+#[derive(Debug, Default)]
+pub struct Fields {
+    pub activity: String,
+    pub alias: String,
+    pub color: String,
+    pub elapsed: String,
+    pub started: String,
+    pub note: String,
+}
+
+impl Fields {
+    pub fn new(
+        tracking: &CurrentTrackingResponse,
+        activities: Option<&ActivityListResponse>,
+        cfg: Option<&Settings>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let activity = activities.and_then(|list| {
+            list.activities
+                .iter()
+                .chain(list.inactive_activities.iter())
+                .find(|a| a.id == tracking.activity_id)
+        });
+        let alias = cfg
+            .and_then(|c| c.alias.as_ref())
+            .and_then(|a| a.activity.as_ref())
+            .and_then(|aliases| {
+                aliases
+                    .iter()
+                    .filter(|(_, id)| **id == tracking.activity_id)
+                    .map(|(alias, _)| alias.to_owned())
+                    .min()
+            });
+
+        Fields {
+            activity: activity.map_or_else(|| tracking.activity_id.to_owned(), |a| a.name.clone()),
+            alias: alias.unwrap_or_default(),
+            color: activity.map(|a| a.color.clone()).unwrap_or_default(),
+            elapsed: format_duration(now - tracking.started_at),
+            started: tracking
+                .started_at
+                .with_timezone(&Local)
+                .format("%H:%M")
+                .to_string(),
+            note: tracking
+                .note
+                .as_ref()
+                .and_then(|n| n.text.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Replaces the placeholders of the template, `\n` becomes a line break
+    fn render(&self, template: &str) -> String {
+        template
+            .replace("\\n", "\n")
+            .replace("{activity}", &self.activity)
+            .replace("{alias}", &self.alias)
+            .replace("{color}", &self.color)
+            .replace("{elapsed}", &self.elapsed)
+            .replace("{started}", &self.started)
+            .replace("{note}", &self.note)
+    }
+}
+
+/// Formats the tracking with a preset or template, nothing is printed if no tracking is running
+/// except for waybar which expects a JSON object
+fn format_status(format: &str, fields: Option<&Fields>) -> String {
+    match (format, fields) {
+        (FORMAT_WAYBAR, Some(f)) => {
+            let tooltip = match f.note.as_str() {
+                "" => format!("{} since {}", f.activity, f.started),
+                note => format!("{} since {}\n{}", f.activity, f.started, note),
+            };
+            json!({
+                "text": f.render(TEMPLATE_WAYBAR_TEXT),
+                "tooltip": tooltip,
+                "class": "tracking",
+                "alt": f.activity,
+            })
+            .to_string()
+        }
+        (FORMAT_WAYBAR, None) => json!({
+            "text": "",
+            "tooltip": "No tracking is running",
+            "class": "idle",
+        })
+        .to_string(),
+        (_, None) => String::new(),
+        (FORMAT_I3BLOCKS, Some(f)) => f.render(TEMPLATE_I3BLOCKS),
+        (FORMAT_TMUX, Some(f)) => f.render(TEMPLATE_TMUX),
+        (template, Some(f)) => f.render(template),
+    }
+}
+
+pub fn handle_match(tmlr: &Timeular, cfg: Option<&Settings>) -> Result<()> {
+    let pending = Journal::new().load()?;
+
+    if tmlr.is_offline() {
+        println!("The Timeular API is unreachable.");
+        let started = pending.iter().rev().find_map(|e| match &e.operation {
+            Operation::StopTracking { .. } => Some(None),
+            Operation::StartTracking {
+                activity_name,
+                started_at,
+                ..
+            } => Some(Some((activity_name, started_at))),
+            Operation::CreateTimeEntry { .. } => None,
+        });
+        if let Some(Some((name, started_at))) = started {
+            println!(
+                "Tracking \"{}\" since {} ({}), recorded offline",
+                name,
+                started_at.with_timezone(&Local).format("%H:%M"),
+                format_duration(Utc::now() - *started_at)
+            );
+        }
+    } else {
+        match tmlr.current_tracking()? {
+            Some(tracking) => {
+                let (color, name) = match Resolver::new(tmlr, cfg).activity(&tracking.activity_id) {
+                    Ok(a) => (a.color, a.name),
+                    Err(_) => (String::new(), tracking.activity_id.to_owned()),
+                };
+                println!(
+                    "{} Tracking \"{}\" since {} ({})",
+                    ansi_foreground(&color, "●"),
+                    name,
+                    tracking.started_at.with_timezone(&Local).format("%H:%M"),
+                    format_duration(Utc::now() - tracking.started_at)
+                );
+                if let Some(text) = tracking.note.and_then(|n| n.text) {
+                    println!("  {}", text);
+                }
+            }
+            None => println!("No tracking is running."),
+        }
+    }
+
+    if !pending.is_empty() {
+        println!(
+            "{} pending operations, run `tmlr {}` to send them:",
+            pending.len(),
+            CMD_SYNC
+        );
+        for entry in pending.iter() {
+            println!("  {}", entry.operation.describe());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeular::{ActivityResponse, Note};
+    use chrono::Duration;
+
+    fn fields() -> Fields {
+        let now = Utc::now();
+        let tracking = CurrentTrackingResponse {
+            activity_id: "1".to_owned(),
+            started_at: now - Duration::minutes(65),
+            note: Some(Note {
+                text: Some("Review".to_owned()),
+                tags: vec![],
+                mentions: vec![],
+            }),
+        };
+        let activities = ActivityListResponse {
+            activities: vec![ActivityResponse {
+                id: "1".to_owned(),
+                name: "Development".to_owned(),
+                color: "#a1b2c3".to_owned(),
+                integration: "zei".to_owned(),
+                space_id: "1".to_owned(),
+                device_side: None,
+            }],
+            inactive_activities: vec![],
+            archived_activities: vec![],
+        };
+        Fields::new(&tracking, Some(&activities), None, now)
+    }
+
+    #[test]
+    fn test_format_status() {
+        let f = fields();
+        assert_eq!(
+            format_status("{activity} ({note}) {elapsed}", Some(&f)),
+            "Development (Review) 1h 05m"
+        );
+        assert_eq!(
+            format_status(FORMAT_I3BLOCKS, Some(&f)),
+            "Development 1h 05m\n1h 05m\n#a1b2c3"
+        );
+        assert_eq!(
+            format_status(FORMAT_TMUX, Some(&f)),
+            "#[fg=#a1b2c3]●#[default] Development 1h 05m"
+        );
+
+        let waybar: serde_json::Value =
+            serde_json::from_str(&format_status(FORMAT_WAYBAR, Some(&f))).unwrap();
+        assert_eq!(waybar["text"], "Development 1h 05m");
+        assert_eq!(waybar["class"], "tracking");
+        assert!(waybar["tooltip"].as_str().unwrap().ends_with("\nReview"));
+
+        let idle: serde_json::Value =
+            serde_json::from_str(&format_status(FORMAT_WAYBAR, None)).unwrap();
+        assert_eq!(idle["class"], "idle");
+        assert_eq!(format_status(FORMAT_TMUX, None), "");
+    }
+}
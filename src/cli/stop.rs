@@ -0,0 +1,39 @@
+use super::{sync::record_offline, CMD_TRACKING};
+use crate::{error::Error::InvalidCommandError, journal::Operation, timeular::Timeular, Result};
+use chrono::Utc;
+use clap::{App, ArgMatches, SubCommand};
+
+pub const CMD_STOP: &str = "stop";
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_STOP)
+        .about("Stops a Resource. Without a subcommand the current tracking will be stopped.")
+        .subcommand(SubCommand::with_name(CMD_TRACKING).about("Stops the current tracking"))
+}
+
+pub fn handle_match<'a>(matches: &ArgMatches<'a>, tmlr: &Timeular) -> Result<()> {
+    match matches.subcommand() {
+        (CMD_TRACKING, Some(_)) | ("", None) => stop_tracking(tmlr),
+        _ => {
+            println!("{}", matches.usage());
+            Err(InvalidCommandError)
+        }
+    }
+}
+
+fn stop_tracking(tmlr: &Timeular) -> Result<()> {
+    let now = Utc::now();
+    let te = match record_offline(tmlr.stop_tracking_at(now), || Operation::StopTracking {
+        stopped_at: now,
+    })? {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    let duration = te.duration.stopped_at - te.duration.started_at;
+    log::info!(
+        "Tracking stopped after {}h {:02}m.",
+        duration.num_hours(),
+        duration.num_minutes() % 60
+    );
+    Ok(())
+}
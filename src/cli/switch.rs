@@ -0,0 +1,65 @@
+use super::{
+    note::{create_note, create_tags_arg},
+    resolver::Resolver,
+    ARG_ACTIVITY, ARG_CREATE_TAGS, ARG_NOTE,
+};
+use crate::{settings::Settings, timeular::Timeular, util::time::format_duration, Result};
+use clap::{App, Arg, ArgMatches};
+
+pub const CMD_SWITCH: &str = "switch";
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_SWITCH)
+        .about("Stops the current tracking and starts tracking another activity at the same time")
+        .arg(
+            Arg::with_name(ARG_ACTIVITY)
+                .help("Defines the activity id, name or alias")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name(ARG_NOTE)
+                .help("Defines a note for the tracking, #tag and @mention are referenced")
+                .long(ARG_NOTE)
+                .short("n")
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(create_tags_arg())
+}
+
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let defaults = cfg.and_then(|c| c.defaults.clone()).unwrap_or_default();
+    let resolver = Resolver::new(tmlr, cfg);
+    let activity = resolver.activity(
+        matches
+            .value_of(ARG_ACTIVITY)
+            .expect("An activity was provided"),
+    )?;
+    let note = create_note(
+        &resolver,
+        matches.value_of(ARG_NOTE).map(|v| v.to_string()),
+        &defaults,
+        matches.is_present(ARG_CREATE_TAGS),
+    )?;
+
+    match tmlr.switch_tracking(&activity.id, Some(note))? {
+        Some(te) => {
+            let previous = resolver
+                .activity(&te.activity_id)
+                .map(|a| a.name)
+                .unwrap_or(te.activity_id);
+            log::info!(
+                "Switched from \"{}\" after {} to \"{}\".",
+                previous,
+                format_duration(te.duration.stopped_at - te.duration.started_at),
+                activity.name
+            );
+        }
+        None => log::info!("Tracking of activity \"{}\" started.", activity.name),
+    }
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use super::{
+    create::CMD_CREATE, start::CMD_START, status::CMD_STATUS, stop::CMD_STOP,
+    time_entry::check_overlap,
+};
+use crate::{
+    error::Error::{ApiUnreachableError, OverlappingTimeEntryError, SyncConflictError},
+    journal::{Journal, JournalEntry, Operation},
+    timeular::Timeular,
+    Result,
+};
+use chrono::{DateTime, Utc};
+use clap::{App, Arg, ArgMatches};
+
+pub const CMD_SYNC: &str = "sync";
+
+/// Commands which work without a connection, changes are recorded in the journal
+pub const OFFLINE_COMMANDS: [&str; 4] = [CMD_START, CMD_STOP, CMD_CREATE, CMD_STATUS];
+
+const ARG_SKIP_CONFLICTS: &str = "skip-conflicts";
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_SYNC)
+        .about("Sends the operations which were recorded while the API was unreachable")
+        .arg(
+            Arg::with_name(ARG_SKIP_CONFLICTS)
+                .help("Drops operations which conflict with the server state instead of stopping")
+                .long(ARG_SKIP_CONFLICTS),
+        )
+}
+
+pub fn handle_match<'a>(matches: &ArgMatches<'a>, tmlr: &Timeular) -> Result<()> {
+    let journal = Journal::new();
+    let entries = journal.load()?;
+    if entries.is_empty() {
+        log::info!("Nothing to sync.");
+        return Ok(());
+    }
+
+    for (idx, entry) in entries.iter().enumerate() {
+        match replay(tmlr, &entry.operation, stopped_at(&entries[idx + 1..])) {
+            Ok(()) => log::info!("Synced {}.", entry.operation.describe()),
+            Err(e @ SyncConflictError(..)) if matches.is_present(ARG_SKIP_CONFLICTS) => {
+                log::info!("Skipped: {}", e)
+            }
+            Err(e) => {
+                if let SyncConflictError(..) = e {
+                    log::info!(
+                        "Fix the server state or use --{} to drop the operation.",
+                        ARG_SKIP_CONFLICTS
+                    );
+                }
+                return Err(e);
+            }
+        }
+        // The journal is updated after every operation, so nothing is sent twice
+        journal.replace(&entries[idx + 1..])?;
+    }
+    Ok(())
+}
+
+/// Records the operation in the journal if the API was unreachable. Returns `None` in this case
+/// and the result of the API call otherwise.
+pub fn record_offline<T>(
+    result: Result<T>,
+    operation: impl FnOnce() -> Operation,
+) -> Result<Option<T>> {
+    match result {
+        Ok(v) => Ok(Some(v)),
+        Err(ApiUnreachableError(e)) => {
+            log::debug!("API unreachable: {}", e);
+            let operation = operation();
+            let description = operation.describe();
+            Journal::new().append(operation)?;
+            log::info!(
+                "The API is unreachable, the {} was recorded and will be sent with `tmlr {}`.",
+                description,
+                CMD_SYNC
+            );
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Applies the operation unless the server state changed in a conflicting way since it was
+/// recorded. A started tracking runs until the stop of the following operations, or until now.
+fn replay(tmlr: &Timeular, operation: &Operation, stopped_at: Option<DateTime<Utc>>) -> Result<()> {
+    let conflict = |reason: String| SyncConflictError(operation.describe(), reason);
+    let check_overlap = |from, to| match check_overlap(tmlr, &[], from, to) {
+        Err(OverlappingTimeEntryError(v)) => Err(conflict(format!("it overlaps with {}", v))),
+        r => r,
+    };
+
+    match operation {
+        Operation::StartTracking {
+            activity_id,
+            started_at,
+            note,
+            ..
+        } => {
+            if let Some(current) = tmlr.current_tracking()? {
+                if current.activity_id == *activity_id && current.started_at == *started_at {
+                    return Ok(());
+                }
+                return Err(conflict(format!(
+                    "another tracking is running since {}",
+                    current.started_at.format("%Y-%m-%d %H:%M UTC")
+                )));
+            }
+            check_overlap(*started_at, stopped_at.unwrap_or_else(Utc::now))?;
+            tmlr.start_tracking_at(activity_id, *started_at, note.clone())?;
+        }
+        Operation::StopTracking { stopped_at } => match tmlr.current_tracking()? {
+            Some(current) if current.started_at < *stopped_at => {
+                tmlr.stop_tracking_at(*stopped_at)?;
+            }
+            Some(_) => {
+                return Err(conflict(
+                    "the running tracking started after this stop".to_owned(),
+                ))
+            }
+            None => return Err(conflict("no tracking is running".to_owned())),
+        },
+        Operation::CreateTimeEntry {
+            activity_id,
+            started_at,
+            stopped_at,
+            note,
+            ..
+        } => {
+            check_overlap(*started_at, *stopped_at)?;
+            tmlr.create_time_entry(activity_id, *started_at, *stopped_at, note.clone())?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns when the following operations stop the tracking
+fn stopped_at(following: &[JournalEntry]) -> Option<DateTime<Utc>> {
+    following.iter().find_map(|e| match e.operation {
+        Operation::StopTracking { stopped_at } => Some(stopped_at),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timeular::Note;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_stopped_at() {
+        let entry = |operation| JournalEntry {
+            recorded_at: Utc.ymd(2021, 6, 1).and_hms(12, 0, 0),
+            operation,
+        };
+        let create = entry(Operation::CreateTimeEntry {
+            activity_id: "1".to_owned(),
+            activity_name: "Development".to_owned(),
+            started_at: Utc.ymd(2021, 6, 1).and_hms(8, 0, 0),
+            stopped_at: Utc.ymd(2021, 6, 1).and_hms(8, 30, 0),
+            note: Note::default(),
+        });
+        let stop = entry(Operation::StopTracking {
+            stopped_at: Utc.ymd(2021, 6, 1).and_hms(10, 0, 0),
+        });
+
+        assert_eq!(stopped_at(&[]), None);
+        assert_eq!(stopped_at(std::slice::from_ref(&create)), None);
+        assert_eq!(
+            stopped_at(&[create, stop]),
+            Some(Utc.ymd(2021, 6, 1).and_hms(10, 0, 0))
+        );
+    }
+}
@@ -0,0 +1,438 @@
+use super::{picker::confirm, resolver::Resolver, ARG_ACTIVITY, CMD_TIME_ENTRY};
+use crate::{
+    error::Error::{
+        InvalidCommandError, InvalidTimeRangeError, OverlappingTimeEntryError,
+        ReferenceNotFoundError,
+    },
+    settings::Settings,
+    timeular::{TimeEntryResponse, Timeular},
+    util::{
+        color::ansi_foreground,
+        time::{format_duration, parse_time_on},
+    },
+    Result,
+};
+use chrono::{DateTime, Duration, Local, Utc};
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+pub const CMD_SPLIT: &str = "split";
+pub const CMD_MERGE: &str = "merge";
+pub const TIME_ENTRY_LAST: &str = "last";
+
+const ARG_TIME_ENTRY: &str = "timeEntry";
+const ARG_FIRST: &str = "first";
+const ARG_SECOND: &str = "second";
+const ARG_AT: &str = "at";
+const ARG_YES: &str = "yes";
+const ARG_BRIDGE: &str = "bridge";
+
+const KIND_TIME_ENTRY: &str = "time entry";
+const LAST_ENTRY_DAYS: i64 = 30;
+/// Gaps up to this long are bridged by a merge without asking for --bridge
+const MAX_MERGE_GAP_SECS: i64 = 60;
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_TIME_ENTRY)
+        .alias("te")
+        .about("Manages time entries")
+        .subcommand(
+            SubCommand::with_name(CMD_SPLIT)
+                .about("Splits a time entry into two at the given time")
+                .arg(
+                    Arg::with_name(ARG_TIME_ENTRY)
+                        .help("Defines the time entry id or \"last\" for the most recent time entry")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name(ARG_AT)
+                        .help("Defines the time where the time entry is split, e.g. \"10:30\" or \"-20m\"")
+                        .long(ARG_AT)
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name(ARG_ACTIVITY)
+                        .help("Defines the activity id, name or alias of the second part. If no activity is passed the activity of the time entry will be taken.")
+                        .long(ARG_ACTIVITY)
+                        .short("a")
+                        .takes_value(true)
+                        .required(false),
+                )
+                .arg(yes_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name(CMD_MERGE)
+                .about("Merges two time entries into one, the activity and the note of the earlier one come first")
+                .arg(
+                    Arg::with_name(ARG_FIRST)
+                        .help("Defines the first time entry id or \"last\"")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name(ARG_SECOND)
+                        .help("Defines the second time entry id or \"last\"")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name(ARG_BRIDGE)
+                        .help("Merges the time entries even if there is a gap between them, the gap is tracked as well")
+                        .long(ARG_BRIDGE),
+                )
+                .arg(yes_arg()),
+        )
+}
+
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    match matches.subcommand() {
+        (CMD_SPLIT, Some(sub_matches)) => handle_split(tmlr, sub_matches, cfg),
+        (CMD_MERGE, Some(sub_matches)) => handle_merge(tmlr, sub_matches, cfg),
+        _ => {
+            println!("{}", matches.usage());
+            Err(InvalidCommandError)
+        }
+    }
+}
+
+fn handle_split<'a>(
+    tmlr: &Timeular,
+    matches: &ArgMatches<'a>,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let entry = find_time_entry(
+        tmlr,
+        matches
+            .value_of(ARG_TIME_ENTRY)
+            .expect("A time entry was provided"),
+    )?;
+    // A bare time is on the day of the entry, not today
+    let day = entry
+        .duration
+        .started_at
+        .with_timezone(&Local)
+        .date()
+        .naive_local();
+    let at = parse_time_on(
+        matches.value_of(ARG_AT).expect("A split time was provided"),
+        Local::now(),
+        day,
+    )?;
+    let (from, to) = (entry.duration.started_at, entry.duration.stopped_at);
+    check_time_range(from, at)?;
+    check_time_range(at, to)?;
+
+    let resolver = Resolver::new(tmlr, cfg);
+    let activity_id = match matches.value_of(ARG_ACTIVITY) {
+        Some(v) => resolver.activity(v)?.id,
+        None => entry.activity_id.to_owned(),
+    };
+
+    println!("Time entry {} will be split into:", entry.id);
+    println!("{}", preview(&resolver, &entry.activity_id, from, at));
+    println!("{}", preview(&resolver, &activity_id, at, to));
+    if !is_confirmed(matches)? {
+        return Ok(());
+    }
+
+    let note = entry.note.clone().unwrap_or_default();
+    tmlr.edit_time_entry(&entry.id, &entry.activity_id, from, at, note.clone())?;
+    let created = match tmlr.create_time_entry(&activity_id, at, to, note.clone()) {
+        Ok(v) => v,
+        Err(e) => {
+            // Restores the original range, so the second part isn't lost
+            if let Err(restore) =
+                tmlr.edit_time_entry(&entry.id, &entry.activity_id, from, to, note)
+            {
+                log::error!(
+                    "Time entry {} could not be restored to {}: {}",
+                    entry.id,
+                    format_range(from, to),
+                    restore
+                );
+            }
+            return Err(e);
+        }
+    };
+    log::info!(
+        "Time entry {} was split, the second part is {}.",
+        entry.id,
+        created.id
+    );
+    Ok(())
+}
+
+fn handle_merge<'a>(
+    tmlr: &Timeular,
+    matches: &ArgMatches<'a>,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let mut entries = [
+        find_time_entry(
+            tmlr,
+            matches
+                .value_of(ARG_FIRST)
+                .expect("A time entry was provided"),
+        )?,
+        find_time_entry(
+            tmlr,
+            matches
+                .value_of(ARG_SECOND)
+                .expect("A time entry was provided"),
+        )?,
+    ];
+    if entries[0].id == entries[1].id {
+        log::info!("A time entry can't be merged with itself.");
+        return Err(InvalidCommandError);
+    }
+    entries.sort_by_key(|e| e.duration.started_at);
+    let (first, second) = (&entries[0], &entries[1]);
+
+    let gap = gap(first, second);
+    if gap > Duration::seconds(MAX_MERGE_GAP_SECS) && !matches.is_present(ARG_BRIDGE) {
+        log::info!(
+            "There is a gap of {} between the time entries, use --{} to merge them anyway.",
+            format_duration(gap),
+            ARG_BRIDGE
+        );
+        return Err(InvalidCommandError);
+    }
+
+    let from = first.duration.started_at;
+    let to = first.duration.stopped_at.max(second.duration.stopped_at);
+    check_overlap(tmlr, &[&first.id, &second.id], from, to)?;
+
+    let resolver = Resolver::new(tmlr, cfg);
+    println!(
+        "Time entries {} and {} will be merged into:",
+        first.id, second.id
+    );
+    println!("{}", preview(&resolver, &first.activity_id, from, to));
+    if first.activity_id != second.activity_id {
+        log::info!(
+            "The activity of time entry {} will be replaced by the activity of time entry {}.",
+            second.id,
+            first.id
+        );
+    }
+    if !is_confirmed(matches)? {
+        return Ok(());
+    }
+
+    let note = first
+        .note
+        .clone()
+        .unwrap_or_default()
+        .merge(second.note.clone().unwrap_or_default());
+    tmlr.edit_time_entry(&first.id, &first.activity_id, from, to, note)?;
+    if let Err(e) = tmlr.delete_time_entry(&second.id) {
+        // Restores the original range, so the entries don't overlap
+        let (original_from, original_to) = (first.duration.started_at, first.duration.stopped_at);
+        let original_note = first.note.clone().unwrap_or_default();
+        if let Err(restore) = tmlr.edit_time_entry(
+            &first.id,
+            &first.activity_id,
+            original_from,
+            original_to,
+            original_note,
+        ) {
+            log::error!(
+                "Time entry {} could not be restored to {}: {}",
+                first.id,
+                format_range(original_from, original_to),
+                restore
+            );
+        }
+        return Err(e);
+    }
+    log::info!("Time entries were merged into {}.", first.id);
+    Ok(())
+}
+
+/// Returns the time between the end of the first and the start of the second entry, which is
+/// zero if they touch or overlap
+fn gap(first: &TimeEntryResponse, second: &TimeEntryResponse) -> Duration {
+    (second.duration.started_at - first.duration.stopped_at).max(Duration::zero())
+}
+
+fn yes_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name(ARG_YES)
+        .help("Skips the confirmation")
+        .long(ARG_YES)
+        .short("y")
+}
+
+fn is_confirmed<'a>(matches: &ArgMatches<'a>) -> Result<bool> {
+    if matches.is_present(ARG_YES) || confirm("Apply the changes?")? {
+        return Ok(true);
+    }
+    log::info!(
+        "Nothing was changed, use --{} to apply the changes without a terminal.",
+        ARG_YES
+    );
+    Ok(false)
+}
+
+fn preview(
+    resolver: &Resolver,
+    activity_id: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> String {
+    let (color, name) = match resolver.activity(activity_id) {
+        Ok(a) => (a.color, a.name),
+        Err(_) => (String::new(), activity_id.to_owned()),
+    };
+    format!(
+        "  {} {}  {} ({})",
+        ansi_foreground(&color, "●"),
+        name,
+        format_range(from, to),
+        format_duration(to - from)
+    )
+}
+
+/// Fetches the time entry with the given id, `last` is resolved to the most recent time entry of
+/// the last 30 days
+pub fn find_time_entry(tmlr: &Timeular, input: &str) -> Result<TimeEntryResponse> {
+    if !input.eq_ignore_ascii_case(TIME_ENTRY_LAST) {
+        return tmlr.get_time_entry(input);
+    }
+
+    let now = Utc::now();
+    tmlr.fetch_time_entries(now - Duration::days(LAST_ENTRY_DAYS), now)?
+        .into_iter()
+        .max_by_key(|e| e.duration.started_at)
+        .ok_or_else(|| ReferenceNotFoundError(KIND_TIME_ENTRY.to_owned(), input.to_owned()))
+}
+
+pub fn check_time_range(from: DateTime<Utc>, to: DateTime<Utc>) -> Result<()> {
+    if from >= to {
+        return Err(InvalidTimeRangeError(format_time(from), format_time(to)));
+    }
+    Ok(())
+}
+
+/// Fails if any other time entry overlaps with the given range, the ignored ids are the entries
+/// which are currently edited
+pub fn check_overlap(
+    tmlr: &Timeular,
+    ignored_ids: &[&str],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<()> {
+    let entries = tmlr.fetch_time_entries(from, to)?;
+    let overlaps = overlapping(&entries, ignored_ids, from, to);
+    if overlaps.is_empty() {
+        return Ok(());
+    }
+
+    Err(OverlappingTimeEntryError(
+        overlaps
+            .iter()
+            .map(|e| {
+                format!(
+                    "{} ({})",
+                    e.id,
+                    format_range(e.duration.started_at, e.duration.stopped_at)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(", "),
+    ))
+}
+
+/// Formats a range in local time, e.g. `2021-06-01 09:00 - 10:30`
+pub fn format_range(from: DateTime<Utc>, to: DateTime<Utc>) -> String {
+    let (from, to) = (from.with_timezone(&Local), to.with_timezone(&Local));
+    if from.date() == to.date() {
+        format!("{} - {}", from.format("%Y-%m-%d %H:%M"), to.format("%H:%M"))
+    } else {
+        format!(
+            "{} - {}",
+            from.format("%Y-%m-%d %H:%M"),
+            to.format("%Y-%m-%d %H:%M")
+        )
+    }
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+/// Entries which only touch the range, e.g. one ends when the other starts, don't overlap
+fn overlapping<'e>(
+    entries: &'e [TimeEntryResponse],
+    ignored_ids: &[&str],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<&'e TimeEntryResponse> {
+    entries
+        .iter()
+        .filter(|e| !ignored_ids.contains(&e.id.as_str()))
+        .filter(|e| e.duration.started_at < to && e.duration.stopped_at > from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.ymd(2021, 6, 1).and_hms(hour, 0, 0)
+    }
+
+    #[test]
+    fn test_overlapping() {
+        let entries = vec![
+            TimeEntryResponse::new("1", "1", at(8), at(9)),
+            TimeEntryResponse::new("2", "1", at(9), at(11)),
+            TimeEntryResponse::new("3", "1", at(11), at(12)),
+        ];
+
+        let ids = |v: Vec<&TimeEntryResponse>| v.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(overlapping(&entries, &[], at(9), at(11))), vec!["2"]);
+        assert_eq!(
+            ids(overlapping(&entries, &["2"], at(9), at(11))),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            ids(overlapping(&entries, &["2"], at(8), at(12))),
+            vec!["1", "3"]
+        );
+        assert!(check_time_range(at(10), at(9)).is_err());
+        assert!(check_time_range(at(9), at(9)).is_err());
+        assert!(check_time_range(at(9), at(10)).is_ok());
+    }
+
+    #[test]
+    fn test_gap() {
+        assert_eq!(
+            gap(
+                &TimeEntryResponse::new("1", "1", at(8), at(9)),
+                &TimeEntryResponse::new("2", "1", at(9), at(10))
+            ),
+            Duration::zero()
+        );
+        assert_eq!(
+            gap(
+                &TimeEntryResponse::new("1", "1", at(8), at(10)),
+                &TimeEntryResponse::new("2", "1", at(9), at(11))
+            ),
+            Duration::zero()
+        );
+        assert_eq!(
+            gap(
+                &TimeEntryResponse::new("1", "1", at(8), at(9)),
+                &TimeEntryResponse::new("2", "1", at(11), at(12))
+            ),
+            Duration::hours(2)
+        );
+    }
+}
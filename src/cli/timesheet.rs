@@ -0,0 +1,402 @@
+use super::{balance::WorkTargets, resolver::Resolver};
+use crate::{
+    settings::Settings,
+    timeular::{TimeEntryResponse, Timeular},
+    util::{
+        color::ansi_foreground,
+        time::{parse_duration, start_of_day},
+    },
+    Result,
+};
+use chrono::{Datelike, Duration, Local, NaiveDate, Weekday};
+use clap::{App, Arg, ArgMatches};
+use std::collections::HashMap;
+use textwrap::core::display_width;
+
+pub const CMD_TIMESHEET: &str = "timesheet";
+
+const ARG_WEEK: &str = "week";
+const ARG_MONTH: &str = "month";
+const ARG_WEEK_START: &str = "week-start";
+const WEEK_START_MONDAY: &str = "mon";
+const WEEK_START_SUNDAY: &str = "sun";
+
+const NAME_MAX_WIDTH: usize = 24;
+const CELL_WIDTH: usize = 7;
+const BELOW_TARGET_COLOR: &str = "#e05252";
+const TOTAL: &str = "Total";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Period {
+    Week,
+    Month,
+}
+
+/// Tracked time per activity (rows) and day (columns)
+#[derive(Debug)]
+struct Grid {
+    days: Vec<NaiveDate>,
+    rows: Vec<Row>,
+}
+
+#[derive(Debug)]
+struct Row {
+    name: String,
+    color: String,
+    cells: Vec<Duration>,
+}
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_TIMESHEET)
+        .about("Shows the tracked hours per activity and day of the current week or month")
+        .arg(
+            Arg::with_name(ARG_WEEK)
+                .help("Shows the current week (default)")
+                .long(ARG_WEEK)
+                .short("w")
+                .conflicts_with(ARG_MONTH),
+        )
+        .arg(
+            Arg::with_name(ARG_MONTH)
+                .help("Shows the current month")
+                .long(ARG_MONTH)
+                .short("m"),
+        )
+        .arg(
+            Arg::with_name(ARG_WEEK_START)
+                .help("Defines the first day of the week, the default can be set in the timesheet settings")
+                .long(ARG_WEEK_START)
+                .takes_value(true)
+                .possible_values(&[WEEK_START_MONDAY, WEEK_START_SUNDAY])
+                .required(false),
+        )
+}
+
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let settings = cfg.and_then(|c| c.timesheet.clone()).unwrap_or_default();
+    let period = if matches.is_present(ARG_MONTH) {
+        Period::Month
+    } else {
+        Period::Week
+    };
+    let week_start = match matches
+        .value_of(ARG_WEEK_START)
+        .or(settings.week_start.as_deref())
+    {
+        Some(WEEK_START_SUNDAY) => Weekday::Sun,
+        _ => Weekday::Mon,
+    };
+
+    let today = Local::today().naive_local();
+    let days = period_days(today, period, week_start);
+    let targets = match cfg.and_then(|c| c.targets.as_ref()) {
+        Some(t) => {
+            let work = WorkTargets::from_settings(t)?;
+            days.iter()
+                .map(|d| Some(work.expected(*d)).filter(|e| *e > Duration::zero()))
+                .collect()
+        }
+        None => {
+            let daily = match &settings.daily_target {
+                Some(v) => Some(parse_duration(v)?),
+                None => None,
+            };
+            days.iter()
+                .map(|d| {
+                    daily.filter(|_| d.weekday() != Weekday::Sat && d.weekday() != Weekday::Sun)
+                })
+                .collect::<Vec<_>>()
+        }
+    };
+    let (first, last) = (days[0], days[days.len() - 1]);
+    let from = start_of_day(first);
+    let to = start_of_day(last.succ());
+    let entries = tmlr.list_time_entries(from, to)?;
+
+    let resolver = Resolver::new(tmlr, cfg);
+    let mut activities = HashMap::new();
+    for e in entries.iter() {
+        activities.entry(e.activity_id.clone()).or_insert_with(|| {
+            resolver
+                .activity(&e.activity_id)
+                .or_else(|_| resolver.archived_activity(&e.activity_id))
+                .map(|a| (a.name, a.color))
+                .unwrap_or_else(|_| (e.activity_id.clone(), String::new()))
+        });
+    }
+
+    let grid = build_grid(&entries, days, &activities);
+    print!("{}", render(&grid, textwrap::termwidth(), &targets, today));
+    Ok(())
+}
+
+/// Returns the days of the current week or month
+fn period_days(today: NaiveDate, period: Period, week_start: Weekday) -> Vec<NaiveDate> {
+    match period {
+        Period::Week => {
+            let offset = (7 + today.weekday().num_days_from_monday()
+                - week_start.num_days_from_monday())
+                % 7;
+            let start = today - Duration::days(offset as i64);
+            (0..7).map(|i| start + Duration::days(i)).collect()
+        }
+        Period::Month => {
+            let start = NaiveDate::from_ymd(today.year(), today.month(), 1);
+            start
+                .iter_days()
+                .take_while(|d| d.month() == today.month())
+                .collect()
+        }
+    }
+}
+
+/// Splits a time entry into the tracked time per local day
+pub fn split_by_day(entry: &TimeEntryResponse) -> Vec<(NaiveDate, Duration)> {
+    let mut result = Vec::new();
+    let mut cursor = entry
+        .duration
+        .started_at
+        .with_timezone(&Local)
+        .naive_local();
+    let end = entry
+        .duration
+        .stopped_at
+        .with_timezone(&Local)
+        .naive_local();
+    while cursor < end {
+        let day_end = cursor.date().succ().and_hms(0, 0, 0).min(end);
+        result.push((cursor.date(), day_end - cursor));
+        cursor = day_end;
+    }
+    result
+}
+
+/// Sums up the time entries per activity and local day, entries running over midnight are split
+fn build_grid(
+    entries: &[TimeEntryResponse],
+    days: Vec<NaiveDate>,
+    activities: &HashMap<String, (String, String)>,
+) -> Grid {
+    let mut sums: HashMap<&str, Vec<Duration>> = HashMap::new();
+    for e in entries {
+        for (day, duration) in split_by_day(e) {
+            if let Some(idx) = days.iter().position(|d| *d == day) {
+                let cells = sums
+                    .entry(&e.activity_id)
+                    .or_insert_with(|| vec![Duration::zero(); days.len()]);
+                cells[idx] = cells[idx] + duration;
+            }
+        }
+    }
+
+    let mut rows: Vec<Row> = sums
+        .into_iter()
+        .map(|(id, cells)| {
+            let (name, color) = activities
+                .get(id)
+                .cloned()
+                .unwrap_or_else(|| (id.to_owned(), String::new()));
+            Row { name, color, cells }
+        })
+        .collect();
+    rows.sort_by(|a, b| {
+        total(&b.cells)
+            .cmp(&total(&a.cells))
+            .then(a.name.cmp(&b.name))
+    });
+
+    Grid { days, rows }
+}
+
+/// Renders the grid, days which don't fit into the width are continued in another block below
+/// The day totals are highlighted if they are below the target of the day
+fn render(grid: &Grid, width: usize, targets: &[Option<Duration>], today: NaiveDate) -> String {
+    let name_width = grid
+        .rows
+        .iter()
+        .map(|r| display_width(&r.name) + 2)
+        .chain(std::iter::once(TOTAL.len()))
+        .max()
+        .unwrap_or_default()
+        .min(NAME_MAX_WIDTH + 2);
+    let total_width = CELL_WIDTH + 1;
+    let per_block = (width.saturating_sub(name_width + total_width) / CELL_WIDTH).max(1);
+
+    let day_totals: Vec<Duration> = (0..grid.days.len())
+        .map(|idx| total(&grid.rows.iter().map(|r| r.cells[idx]).collect::<Vec<_>>()))
+        .collect();
+    let is_below_target = |idx: usize| matches!(targets[idx], Some(t) if grid.days[idx] <= today && day_totals[idx] < t);
+
+    let indices: Vec<usize> = (0..grid.days.len()).collect();
+    let blocks: Vec<&[usize]> = indices.chunks(per_block).collect();
+    let mut out = String::new();
+    for (block_idx, block) in blocks.iter().enumerate() {
+        let is_last = block_idx == blocks.len() - 1;
+        if block_idx > 0 {
+            out.push('\n');
+        }
+
+        out.push_str(&" ".repeat(name_width));
+        for idx in block.iter() {
+            out.push_str(&format!(
+                "{:>w$}",
+                grid.days[*idx].format("%a %d").to_string(),
+                w = CELL_WIDTH
+            ));
+        }
+        if is_last {
+            out.push_str(&format!("{:>w$}", TOTAL, w = total_width));
+        }
+        out.push('\n');
+
+        for row in grid.rows.iter() {
+            let name = truncate(&row.name, name_width - 2);
+            out.push_str(&ansi_foreground(&row.color, "●"));
+            out.push(' ');
+            out.push_str(&name);
+            out.push_str(&" ".repeat(name_width - 2 - display_width(&name)));
+            for idx in block.iter() {
+                out.push_str(&format!(
+                    "{:>w$}",
+                    format_hours(row.cells[*idx]),
+                    w = CELL_WIDTH
+                ));
+            }
+            if is_last {
+                out.push_str(&format!(
+                    "{:>w$}",
+                    format_hours(total(&row.cells)),
+                    w = total_width
+                ));
+            }
+            out.push('\n');
+        }
+
+        out.push_str(&format!("{:w$}", TOTAL, w = name_width));
+        for idx in block.iter() {
+            let cell = format!("{:>w$}", format_hours(day_totals[*idx]), w = CELL_WIDTH);
+            if is_below_target(*idx) {
+                out.push_str(&ansi_foreground(BELOW_TARGET_COLOR, &cell));
+            } else {
+                out.push_str(&cell);
+            }
+        }
+        if is_last {
+            out.push_str(&format!(
+                "{:>w$}",
+                format_hours(total(&day_totals)),
+                w = total_width
+            ));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub fn total(durations: &[Duration]) -> Duration {
+    durations.iter().fold(Duration::zero(), |acc, d| acc + *d)
+}
+
+/// Formats a duration as `h:mm`, empty cells are shown as `-`
+pub fn format_hours(duration: Duration) -> String {
+    let minutes = duration.num_minutes();
+    if minutes == 0 {
+        return "-".to_owned();
+    }
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_owned();
+    }
+
+    let mut result = String::new();
+    for c in text.chars() {
+        if display_width(&result) + display_width(&c.to_string()) >= width {
+            break;
+        }
+        result.push(c);
+    }
+    result.push('…');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDateTime, TimeZone, Utc};
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(2021, 6, day)
+    }
+
+    fn local(day: u32, hour: u32, min: u32) -> chrono::DateTime<Utc> {
+        let naive: NaiveDateTime = date(day).and_hms(hour, min, 0);
+        Local
+            .from_local_datetime(&naive)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_period_days() {
+        // 2021-06-02 is a Wednesday
+        let week = period_days(date(2), Period::Week, Weekday::Mon);
+        assert_eq!(week.first(), Some(&NaiveDate::from_ymd(2021, 5, 31)));
+        assert_eq!(week.len(), 7);
+
+        let week = period_days(date(2), Period::Week, Weekday::Sun);
+        assert_eq!(week.first(), Some(&NaiveDate::from_ymd(2021, 5, 30)));
+
+        let month = period_days(date(2), Period::Month, Weekday::Mon);
+        assert_eq!(month.first(), Some(&date(1)));
+        assert_eq!(month.last(), Some(&date(30)));
+    }
+
+    #[test]
+    fn test_build_grid_and_render() {
+        let days = period_days(date(2), Period::Week, Weekday::Mon);
+        let entries = vec![
+            TimeEntryResponse::new("1", "1", local(1, 9, 0), local(1, 11, 30)),
+            TimeEntryResponse::new("1", "2", local(1, 23, 0), local(2, 1, 0)),
+            TimeEntryResponse::new("1", "1", local(2, 8, 0), local(2, 9, 0)),
+            TimeEntryResponse::new("1", "1", local(20, 8, 0), local(20, 9, 0)),
+        ];
+        let mut activities = HashMap::new();
+        activities.insert("1".to_owned(), ("Development".to_owned(), String::new()));
+
+        let grid = build_grid(&entries, days, &activities);
+        assert_eq!(grid.rows.len(), 2);
+        assert_eq!(grid.rows[0].name, "Development");
+        assert_eq!(grid.rows[0].cells[1], Duration::minutes(150));
+        assert_eq!(grid.rows[0].cells[2], Duration::hours(1));
+        assert_eq!(grid.rows[1].name, "2");
+        assert_eq!(grid.rows[1].cells[1], Duration::hours(1));
+        assert_eq!(grid.rows[1].cells[2], Duration::hours(1));
+
+        let output = render(&grid, 200, &[None; 7], date(6));
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].ends_with("Sun 06   Total"));
+        assert!(lines[1].contains("Development"));
+        assert!(lines[1].ends_with("3:30"));
+        assert!(lines[3].starts_with("Total"));
+        assert!(lines[3].ends_with("5:30"));
+
+        // Two days per block, the total column is only part of the last one
+        let output = render(&grid, 40, &[None; 7], date(6));
+        assert_eq!(output.lines().count(), 4 * 4 + 3);
+        assert_eq!(output.matches(TOTAL).count(), 4 + 1);
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("Development", 20), "Development");
+        assert_eq!(truncate("Development", 5), "Deve…");
+    }
+}
@@ -0,0 +1,215 @@
+use super::{
+    picker::{confirm, is_interactive},
+    resolver::Resolver,
+};
+use crate::{
+    idle::{CommandSource, IdleSource, LogindSource},
+    settings::Settings,
+    timeular::{Note, Timeular},
+    util::time::{format_duration, parse_duration},
+    Result,
+};
+use chrono::{DateTime, Duration, Local, Utc};
+use clap::{App, Arg, ArgMatches};
+use std::thread;
+
+pub const CMD_WATCH: &str = "watch";
+
+const ARG_THRESHOLD: &str = "threshold";
+const ARG_COMMAND: &str = "command";
+const ARG_ACTION: &str = "action";
+
+const ACTION_PROMPT: &str = "prompt";
+const ACTION_TRIM: &str = "trim";
+
+const DEFAULT_THRESHOLD: &str = "10m";
+const DEFAULT_INTERVAL: &str = "30s";
+
+pub fn create_commands<'a, 'b>() -> App<'a, 'b> {
+    App::new(CMD_WATCH)
+        .about("Watches the idle time and discards it from the running tracking (Linux only)")
+        .arg(
+            Arg::with_name(ARG_THRESHOLD)
+                .help("Idle time after which the tracking is handled, defaults to 10m")
+                .long(ARG_THRESHOLD)
+                .short("t")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(ARG_COMMAND)
+                .help("Command printing the idle time in milliseconds, e.g. xprintidle. Without a command logind's IdleSinceHint is used.")
+                .long(ARG_COMMAND)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name(ARG_ACTION)
+                .help("prompt asks whether to discard the idle time (trim without a terminal), trim stops the tracking at the last activity")
+                .long(ARG_ACTION)
+                .possible_values(&[ACTION_PROMPT, ACTION_TRIM])
+                .takes_value(true),
+        )
+}
+
+/// Every idle period is only handled once, it ends with the next input
+#[derive(Debug, Default)]
+struct IdlePeriod {
+    handled: bool,
+}
+
+impl IdlePeriod {
+    /// Returns `true` the first time the idle time of the period reaches the threshold
+    fn exceeded(&mut self, idle: Duration, threshold: Duration) -> bool {
+        if idle < threshold {
+            self.handled = false;
+            return false;
+        }
+        !std::mem::replace(&mut self.handled, true)
+    }
+}
+
+pub fn handle_match<'a>(
+    matches: &ArgMatches<'a>,
+    tmlr: &Timeular,
+    cfg: Option<&Settings>,
+) -> Result<()> {
+    let settings = cfg.and_then(|c| c.idle.clone()).unwrap_or_default();
+    let threshold = parse_duration(
+        matches
+            .value_of(ARG_THRESHOLD)
+            .or(settings.threshold.as_deref())
+            .unwrap_or(DEFAULT_THRESHOLD),
+    )?;
+    let interval = parse_duration(settings.interval.as_deref().unwrap_or(DEFAULT_INTERVAL))?;
+    let mut action = matches
+        .value_of(ARG_ACTION)
+        .or(settings.action.as_deref())
+        .unwrap_or(ACTION_PROMPT);
+    // Nobody could answer, e.g. when run as a service, so the idle time would never be handled
+    if action == ACTION_PROMPT && !is_interactive() {
+        log::warn!(
+            "No terminal attached to ask, idle trackings are trimmed instead. Use --{} {} to silence this warning.",
+            ARG_ACTION,
+            ACTION_TRIM
+        );
+        action = ACTION_TRIM;
+    }
+
+    let source: Box<dyn IdleSource> = match matches
+        .value_of(ARG_COMMAND)
+        .or(settings.command.as_deref())
+    {
+        Some(c) => Box::new(CommandSource::new(c.to_owned())),
+        None => Box::new(LogindSource::new()),
+    };
+    // Fails early if the source is not available
+    source.idle_time()?;
+
+    log::info!(
+        "Watching the idle time, trackings idle for {} are handled.",
+        format_duration(threshold)
+    );
+
+    let mut period = IdlePeriod::default();
+    loop {
+        thread::sleep(interval.to_std().unwrap_or_default());
+
+        let idle = match source.idle_time() {
+            Ok(v) => v,
+            Err(e) => {
+                log::debug!("{}", e);
+                continue;
+            }
+        };
+        if !period.exceeded(idle, threshold) {
+            continue;
+        }
+
+        let tracking = match tmlr.current_tracking() {
+            Ok(Some(v)) => v,
+            Ok(None) => continue,
+            Err(e) => {
+                log::error!("{}", e);
+                continue;
+            }
+        };
+        let last_active = Utc::now() - idle;
+        // The tracking was started while idle, e.g. on the tracker or another device
+        if last_active <= tracking.started_at {
+            continue;
+        }
+
+        let name = Resolver::new(tmlr, cfg)
+            .activity(&tracking.activity_id)
+            .map(|a| a.name)
+            .unwrap_or_else(|_| tracking.activity_id.to_owned());
+        let result = match action {
+            ACTION_TRIM => tmlr.stop_tracking_at(last_active).map(|_| {
+                log::info!(
+                    "Idle since {}, tracking \"{}\" was stopped at the last activity.",
+                    format_time(last_active),
+                    name
+                );
+            }),
+            _ => discard_idle_time(
+                tmlr,
+                &name,
+                last_active,
+                tracking.activity_id,
+                tracking.note,
+            ),
+        };
+        if let Err(e) = result {
+            log::error!("{}", e);
+        }
+    }
+}
+
+/// Asks whether the idle time should be discarded, which stops the tracking at the last activity
+/// and starts it again now, as the user is back when the question is answered
+fn discard_idle_time(
+    tmlr: &Timeular,
+    name: &str,
+    last_active: DateTime<Utc>,
+    activity_id: String,
+    note: Option<Note>,
+) -> Result<()> {
+    let prompt = format!(
+        "Idle since {} while tracking \"{}\". Discard the idle time?",
+        format_time(last_active),
+        name
+    );
+    if !confirm(&prompt)? {
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    let entry = tmlr.stop_tracking_at(last_active)?;
+    tmlr.start_tracking_at(&activity_id, now, note)?;
+    log::info!(
+        "Discarded {} of idle time, tracking \"{}\" continues.",
+        format_duration(now - entry.duration.stopped_at),
+        name
+    );
+    Ok(())
+}
+
+fn format_time(at: DateTime<Utc>) -> String {
+    at.with_timezone(&Local).format("%H:%M").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_period() {
+        let threshold = Duration::minutes(10);
+        let mut period = IdlePeriod::default();
+        assert!(!period.exceeded(Duration::minutes(5), threshold));
+        assert!(period.exceeded(Duration::minutes(10), threshold));
+        assert!(!period.exceeded(Duration::minutes(11), threshold));
+
+        assert!(!period.exceeded(Duration::seconds(1), threshold));
+        assert!(period.exceeded(Duration::minutes(12), threshold));
+    }
+}
@@ -0,0 +1,209 @@
+//! Background daemon which keeps one authenticated session, CLI commands talk to it through a
+//! Unix domain socket using JSON-RPC 2.0 with one message per line
+
+use crate::{
+    error::Error::DaemonError,
+    settings::get_default_cache_dir,
+    timeular::{ActivityListResponse, CurrentTrackingResponse, TagsAndMentionsResponse},
+    Result,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    cell::Cell,
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+pub mod server;
+
+pub const METHOD_PING: &str = "ping";
+pub const METHOD_TOKEN: &str = "token";
+pub const METHOD_CURRENT_TRACKING: &str = "current_tracking";
+pub const METHOD_LIST_ACTIVITIES: &str = "list_activities";
+pub const METHOD_LIST_TAGS_AND_MENTIONS: &str = "list_tags_and_mentions";
+pub const METHOD_INVALIDATE: &str = "invalidate";
+pub const METHOD_SHUTDOWN: &str = "shutdown";
+
+const JSON_RPC_VERSION: &str = "2.0";
+const SOCKET_DIR_NAME: &str = "daemon";
+const SOCKET_FILE_NAME: &str = "daemon.sock";
+const TIMEOUT_SECS: u64 = 5;
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Request {
+    pub jsonrpc: String,
+    pub id: u64,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Response {
+    pub jsonrpc: String,
+    pub id: u64,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub error: Option<RpcError>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+/// Answer of [`METHOD_PING`]
+#[derive(Deserialize, Serialize, Debug)]
+pub struct PingResponse {
+    pub pid: u32,
+    pub uptime_secs: u64,
+}
+
+impl Response {
+    pub fn result(id: u64, result: Value) -> Self {
+        Response {
+            jsonrpc: JSON_RPC_VERSION.to_owned(),
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    pub fn error(id: u64, code: i64, message: String) -> Self {
+        Response {
+            jsonrpc: JSON_RPC_VERSION.to_owned(),
+            id,
+            result: None,
+            error: Some(RpcError { code, message }),
+        }
+    }
+}
+
+pub struct DaemonClient {
+    path: PathBuf,
+    next_id: Cell<u64>,
+}
+
+impl DaemonClient {
+    /// Connects to the running daemon, fails if no daemon answers on the socket
+    pub fn connect() -> Result<Self> {
+        DaemonClient::connect_to(&socket_path())
+    }
+
+    pub fn connect_to(path: &Path) -> Result<Self> {
+        let client = DaemonClient {
+            path: path.to_owned(),
+            next_id: Cell::new(1),
+        };
+        client.ping()?;
+        Ok(client)
+    }
+
+    pub fn ping(&self) -> Result<PingResponse> {
+        self.call(METHOD_PING, Value::Null)
+    }
+
+    /// Returns the token of the session, fails if the daemon is logged in with another API key
+    pub fn token(&self, api_key: &str) -> Result<String> {
+        self.call(METHOD_TOKEN, json!({ "api_key": api_key }))
+    }
+
+    pub fn current_tracking(&self) -> Result<Option<CurrentTrackingResponse>> {
+        self.call(METHOD_CURRENT_TRACKING, Value::Null)
+    }
+
+    pub fn list_activities(&self) -> Result<ActivityListResponse> {
+        self.call(METHOD_LIST_ACTIVITIES, Value::Null)
+    }
+
+    pub fn list_tags_and_mentions(&self) -> Result<TagsAndMentionsResponse> {
+        self.call(METHOD_LIST_TAGS_AND_MENTIONS, Value::Null)
+    }
+
+    pub fn invalidate(&self, prefix: &str) -> Result<()> {
+        self.call::<Value>(METHOD_INVALIDATE, json!({ "prefix": prefix }))
+            .map(|_| ())
+    }
+
+    pub fn shutdown(&self) -> Result<()> {
+        self.call::<Value>(METHOD_SHUTDOWN, Value::Null).map(|_| ())
+    }
+
+    fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        let request = Request {
+            jsonrpc: JSON_RPC_VERSION.to_owned(),
+            id,
+            method: method.to_owned(),
+            params,
+        };
+
+        let error = |e: std::io::Error| DaemonError(e.to_string());
+        let mut stream = UnixStream::connect(&self.path).map_err(error)?;
+        stream
+            .set_read_timeout(Some(Duration::from_secs(TIMEOUT_SECS)))
+            .map_err(error)?;
+        let line = serde_json::to_string(&request).map_err(|e| DaemonError(e.to_string()))?;
+        writeln!(stream, "{}", line).map_err(error)?;
+
+        let mut answer = String::new();
+        BufReader::new(stream)
+            .read_line(&mut answer)
+            .map_err(error)?;
+        let response: Response =
+            serde_json::from_str(&answer).map_err(|e| DaemonError(e.to_string()))?;
+        if let Some(e) = response.error {
+            return Err(DaemonError(e.message));
+        }
+        serde_json::from_value(response.result.unwrap_or(Value::Null))
+            .map_err(|e| DaemonError(e.to_string()))
+    }
+}
+
+/// The socket is kept in its own dir, which only the user may enter
+pub fn socket_path() -> PathBuf {
+    Path::new(&get_default_cache_dir())
+        .join(SOCKET_DIR_NAME)
+        .join(SOCKET_FILE_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{os::unix::net::UnixListener, thread};
+
+    #[test]
+    fn test_client_call() {
+        let dir = std::env::temp_dir().join(format!("tmlr-daemon-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SOCKET_FILE_NAME);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = thread::spawn(move || {
+            for _ in 0..2 {
+                let (stream, _) = listener.accept().unwrap();
+                let mut line = String::new();
+                BufReader::new(&stream).read_line(&mut line).unwrap();
+                let request: Request = serde_json::from_str(&line).unwrap();
+                let response = match request.method.as_str() {
+                    METHOD_PING => {
+                        Response::result(request.id, json!({"pid": 1, "uptime_secs": 2}))
+                    }
+                    _ => Response::error(request.id, -32601, "Unknown method".to_owned()),
+                };
+                writeln!(&stream, "{}", serde_json::to_string(&response).unwrap()).unwrap();
+            }
+        });
+
+        let client = DaemonClient::connect_to(&path).unwrap();
+        assert!(matches!(client.token("key"), Err(DaemonError(m)) if m == "Unknown method"));
+        server.join().unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+use super::{
+    socket_path, DaemonClient, PingResponse, Request, Response, METHOD_CURRENT_TRACKING,
+    METHOD_INVALIDATE, METHOD_LIST_ACTIVITIES, METHOD_LIST_TAGS_AND_MENTIONS, METHOD_PING,
+    METHOD_SHUTDOWN, METHOD_TOKEN,
+};
+use crate::{
+    cache::KEY_TRACKING,
+    error::Error::DaemonError,
+    timeular::{CurrentTrackingResponse, Timeular},
+    Result,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    cell::RefCell,
+    fs::{self, DirBuilder, Permissions},
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::{DirBuilderExt, PermissionsExt},
+        net::{UnixListener, UnixStream},
+    },
+    time::{Duration, Instant},
+};
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const SERVER_ERROR: i64 = -32000;
+
+/// The current tracking is kept for a few seconds, changes by the CLI invalidate it
+const TRACKING_TTL: Duration = Duration::from_secs(15);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+struct Server<'a> {
+    tmlr: &'a Timeular<'a>,
+    started: Instant,
+    tracking: RefCell<Option<(Instant, Option<CurrentTrackingResponse>)>>,
+}
+
+/// Serves requests on the socket until a shutdown is requested. A socket which is left behind
+/// by a crashed daemon is replaced.
+pub fn run(tmlr: &Timeular) -> Result<()> {
+    let path = socket_path();
+    if path.exists() {
+        if DaemonClient::connect_to(&path).is_ok() {
+            return Err(DaemonError(format!(
+                "a daemon is already running on {}",
+                path.display()
+            )));
+        }
+        fs::remove_file(&path).map_err(|e| DaemonError(e.to_string()))?;
+    }
+    // The socket hands out the session token, so only the user may connect. The socket is
+    // created with the umask, so its dir has to be closed before it is bound.
+    if let Some(dir) = path.parent() {
+        DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .map_err(|e| DaemonError(e.to_string()))?;
+        fs::set_permissions(dir, Permissions::from_mode(0o700))
+            .map_err(|e| DaemonError(e.to_string()))?;
+    }
+
+    let listener = UnixListener::bind(&path).map_err(|e| DaemonError(e.to_string()))?;
+    fs::set_permissions(&path, Permissions::from_mode(0o600))
+        .map_err(|e| DaemonError(e.to_string()))?;
+    log::info!("Daemon is listening on {}.", path.display());
+
+    let server = Server {
+        tmlr,
+        started: Instant::now(),
+        tracking: RefCell::new(None),
+    };
+    for stream in listener.incoming() {
+        match stream {
+            Ok(s) => {
+                if server.handle_connection(s) {
+                    break;
+                }
+            }
+            Err(e) => log::debug!("Unable to accept connection: {}", e),
+        }
+    }
+
+    fs::remove_file(&path).map_err(|e| DaemonError(e.to_string()))?;
+    log::info!("Daemon stopped.");
+    Ok(())
+}
+
+impl Server<'_> {
+    /// Answers all requests of the connection, returns `true` if a shutdown was requested
+    fn handle_connection(&self, stream: UnixStream) -> bool {
+        if let Err(e) = stream.set_read_timeout(Some(CLIENT_TIMEOUT)) {
+            log::debug!("Unable to set timeout: {}", e);
+        }
+        let mut writer = match stream.try_clone() {
+            Ok(v) => v,
+            Err(e) => {
+                log::debug!("Unable to answer connection: {}", e);
+                return false;
+            }
+        };
+
+        let mut shutdown = false;
+        for line in BufReader::new(stream).lines() {
+            let line = match line {
+                Ok(v) => v,
+                Err(e) => {
+                    log::debug!("Unable to read request: {}", e);
+                    break;
+                }
+            };
+            let response = match serde_json::from_str::<Request>(&line) {
+                Ok(request) => {
+                    log::debug!("Request {} {}", request.id, request.method);
+                    shutdown |= request.method == METHOD_SHUTDOWN;
+                    self.dispatch(request)
+                }
+                Err(e) => Response::error(0, PARSE_ERROR, e.to_string()),
+            };
+
+            let answer = serde_json::to_string(&response).expect("Responses can be serialized");
+            if let Err(e) = writeln!(writer, "{}", answer) {
+                log::debug!("Unable to send response: {}", e);
+                break;
+            }
+        }
+        shutdown
+    }
+
+    fn dispatch(&self, request: Request) -> Response {
+        let id = request.id;
+        let result = match request.method.as_str() {
+            METHOD_PING => to_value(Ok(PingResponse {
+                pid: std::process::id(),
+                uptime_secs: self.started.elapsed().as_secs(),
+            })),
+            METHOD_TOKEN => {
+                let api_key = request.params["api_key"].as_str().unwrap_or_default();
+                to_value(self.tmlr.session_token(api_key))
+            }
+            METHOD_CURRENT_TRACKING => to_value(self.current_tracking()),
+            METHOD_LIST_ACTIVITIES => to_value(self.tmlr.list_activities()),
+            METHOD_LIST_TAGS_AND_MENTIONS => to_value(self.tmlr.list_tags_and_mentions()),
+            METHOD_INVALIDATE => {
+                let prefix = request.params["prefix"].as_str().unwrap_or_default();
+                if KEY_TRACKING.starts_with(prefix) {
+                    self.tracking.replace(None);
+                }
+                Ok(Value::Null)
+            }
+            METHOD_SHUTDOWN => Ok(Value::Null),
+            _ => {
+                return Response::error(
+                    id,
+                    METHOD_NOT_FOUND,
+                    format!("Unknown method \"{}\"", request.method),
+                )
+            }
+        };
+
+        match result {
+            Ok(v) => Response::result(id, v),
+            Err(e) => Response::error(id, SERVER_ERROR, e.to_string()),
+        }
+    }
+
+    fn current_tracking(&self) -> Result<Option<CurrentTrackingResponse>> {
+        if let Some((fetched, tracking)) = self.tracking.borrow().as_ref() {
+            if fetched.elapsed() < TRACKING_TTL {
+                return Ok(tracking.clone());
+            }
+        }
+
+        let tracking = self.tmlr.current_tracking()?;
+        self.tracking
+            .replace(Some((Instant::now(), tracking.clone())));
+        Ok(tracking)
+    }
+}
+
+fn to_value<T: Serialize>(result: Result<T>) -> Result<Value> {
+    result.and_then(|v| serde_json::to_value(v).map_err(|e| DaemonError(e.to_string())))
+}
@@ -5,6 +5,8 @@ use thiserror::Error;
 pub enum Error {
     #[error("An error occurred while calling {0} with error: {1}")]
     TimeularApiError(String, String),
+    #[error("The Timeular API is unreachable: {0}")]
+    ApiUnreachableError(String),
     #[error("Unable to parse response while {0}")]
     ParseJsonError(String),
     #[error("No authentication data found")]
@@ -15,4 +17,36 @@ pub enum Error {
     ConfigError(#[from] ConfigurationError),
     #[error("Couldn't determine default space")]
     NoDefaultSpaceFound,
+    #[error("Unable to find {0} \"{1}\"")]
+    ReferenceNotFoundError(String, String),
+    #[error("The {0} \"{1}\" is ambiguous, it matches: {2}")]
+    AmbiguousReferenceError(String, String, String),
+    #[error("Unable to parse time \"{0}\"")]
+    InvalidTimeError(String),
+    #[error("The start {0} has to be before the end {1}")]
+    InvalidTimeRangeError(String, String),
+    #[error("The time entry overlaps with: {0}")]
+    OverlappingTimeEntryError(String),
+    #[error("There is already a running tracking of activity \"{0}\"")]
+    TrackingRunningError(String),
+    #[error("Unable to parse color \"{0}\", expected a hex value like #1a2b3c, a CSS color name or \"random\"")]
+    InvalidColorError(String),
+    #[error("Unable to parse date \"{0}\", expected a date like 2021-12-24 or a range like 2021-12-24..2021-12-31")]
+    InvalidDateError(String),
+    #[error("Unable to read the holiday calendar {0}: {1}")]
+    CalendarFileError(String, String),
+    #[error("Unable to use the cache: {0}")]
+    CacheError(String),
+    #[error("Unable to use the offline journal: {0}")]
+    JournalError(String),
+    #[error("The pending {0} conflicts with the server state: {1}")]
+    SyncConflictError(String, String),
+    #[error("Unable to talk to the daemon: {0}")]
+    DaemonError(String),
+    #[error("Git failed: {0}")]
+    GitError(String),
+    #[error("Unable to read the idle time: {0}")]
+    IdleSourceError(String),
+    #[error("Unable to interact with the terminal: {0}")]
+    TerminalError(String),
 }
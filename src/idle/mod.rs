@@ -0,0 +1,149 @@
+//! Sources of the time since the last user input, used to detect trackings which keep running
+//! while nobody is at the computer
+
+use crate::{error::Error::IdleSourceError, Result};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use std::{env, process::Command};
+
+pub trait IdleSource {
+    /// Returns how long the user has been idle
+    fn idle_time(&self) -> Result<Duration>;
+}
+
+/// Runs a command which prints the idle time in milliseconds, e.g. `xprintidle`
+pub struct CommandSource {
+    command: String,
+}
+
+impl CommandSource {
+    pub fn new(command: String) -> Self {
+        CommandSource { command }
+    }
+}
+
+impl IdleSource for CommandSource {
+    fn idle_time(&self) -> Result<Duration> {
+        let output = run(
+            Command::new("sh").arg("-c").arg(&self.command),
+            &self.command,
+        )?;
+        parse_milliseconds(&output)
+    }
+}
+
+/// Reads the `IdleHint` and `IdleSinceHint` of the logind session, which are set by most
+/// desktop environments and screen lockers
+pub struct LogindSource {
+    session: String,
+}
+
+impl LogindSource {
+    /// Uses the session of `$XDG_SESSION_ID`, or the session of the caller if it's not set
+    pub fn new() -> Self {
+        LogindSource {
+            session: env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_owned()),
+        }
+    }
+}
+
+impl IdleSource for LogindSource {
+    fn idle_time(&self) -> Result<Duration> {
+        let output = run(
+            Command::new("loginctl")
+                .arg("show-session")
+                .arg(&self.session)
+                .arg("-p")
+                .arg("IdleHint")
+                .arg("-p")
+                .arg("IdleSinceHint"),
+            "loginctl",
+        )?;
+        parse_logind(&output, Utc::now())
+    }
+}
+
+fn run(command: &mut Command, name: &str) -> Result<String> {
+    let output = command
+        .output()
+        .map_err(|e| IdleSourceError(format!("{}: {}", name, e)))?;
+    if !output.status.success() {
+        return Err(IdleSourceError(format!(
+            "{}: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn parse_milliseconds(output: &str) -> Result<Duration> {
+    output
+        .trim()
+        .parse::<i64>()
+        .map(Duration::milliseconds)
+        .map_err(|_| IdleSourceError(format!("expected milliseconds, got \"{}\"", output.trim())))
+}
+
+/// Parses the properties of `loginctl show-session`, `IdleSinceHint` are the microseconds since
+/// the epoch and only valid while `IdleHint` is set
+fn parse_logind(output: &str, now: DateTime<Utc>) -> Result<Duration> {
+    let property = |name: &str| {
+        output
+            .lines()
+            .find_map(|l| l.strip_prefix(name)?.strip_prefix('='))
+            .map(str::trim)
+    };
+
+    match property("IdleHint") {
+        Some("yes") => {}
+        Some(_) => return Ok(Duration::zero()),
+        None => return Err(IdleSourceError("logind reports no IdleHint".to_owned())),
+    }
+
+    let micros = property("IdleSinceHint")
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|v| *v > 0)
+        .ok_or_else(|| IdleSourceError("logind reports no IdleSinceHint".to_owned()))?;
+    let since = DateTime::<Utc>::from_utc(
+        NaiveDateTime::from_timestamp(micros / 1_000_000, (micros % 1_000_000) as u32 * 1000),
+        Utc,
+    );
+    Ok((now - since).max(Duration::zero()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_milliseconds() {
+        assert_eq!(
+            parse_milliseconds("61000\n").unwrap(),
+            Duration::seconds(61)
+        );
+        assert!(parse_milliseconds("idle").is_err());
+    }
+
+    #[test]
+    fn test_parse_logind() {
+        let now = Utc.ymd(2021, 6, 1).and_hms(12, 0, 0);
+        let since = (now - Duration::minutes(12)).timestamp() * 1_000_000;
+
+        let output = format!("IdleHint=yes\nIdleSinceHint={}\n", since);
+        assert_eq!(parse_logind(&output, now).unwrap(), Duration::minutes(12));
+
+        let output = format!("IdleHint=no\nIdleSinceHint={}\n", since);
+        assert_eq!(parse_logind(&output, now).unwrap(), Duration::zero());
+
+        assert!(parse_logind("IdleHint=yes\nIdleSinceHint=0\n", now).is_err());
+        assert!(parse_logind("", now).is_err());
+    }
+
+    #[test]
+    fn test_command_source() {
+        let source = CommandSource::new("echo 1500".to_owned());
+        assert_eq!(source.idle_time().unwrap(), Duration::milliseconds(1500));
+        assert!(CommandSource::new("exit 1".to_owned()).idle_time().is_err());
+    }
+}
@@ -0,0 +1,179 @@
+//! Append-only journal of operations which were recorded while the API was unreachable
+
+use crate::{error::Error::JournalError, settings::get_default_cfg_dir, timeular::Note, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, create_dir_all, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+const JOURNAL_FILE_NAME: &str = "journal.jsonl";
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    StartTracking {
+        activity_id: String,
+        activity_name: String,
+        started_at: DateTime<Utc>,
+        note: Option<Note>,
+    },
+    StopTracking {
+        stopped_at: DateTime<Utc>,
+    },
+    CreateTimeEntry {
+        activity_id: String,
+        activity_name: String,
+        started_at: DateTime<Utc>,
+        stopped_at: DateTime<Utc>,
+        note: Note,
+    },
+}
+
+impl Operation {
+    /// A short description for status output and conflicts
+    pub fn describe(&self) -> String {
+        let time = |t: &DateTime<Utc>| t.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+        match self {
+            Operation::StartTracking {
+                activity_name,
+                started_at,
+                ..
+            } => format!("start of \"{}\" at {}", activity_name, time(started_at)),
+            Operation::StopTracking { stopped_at } => {
+                format!("stop of the tracking at {}", time(stopped_at))
+            }
+            Operation::CreateTimeEntry {
+                activity_name,
+                started_at,
+                stopped_at,
+                ..
+            } => format!(
+                "time entry of \"{}\" from {} to {}",
+                activity_name,
+                time(started_at),
+                time(stopped_at)
+            ),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub recorded_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub operation: Operation,
+}
+
+/// The journal is stored as one JSON object per line in the configuration dir, so it survives
+/// clearing the cache
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Journal::with_path(&Path::new(&get_default_cfg_dir()).join(JOURNAL_FILE_NAME))
+    }
+
+    pub fn with_path(path: &Path) -> Self {
+        Journal {
+            path: path.to_owned(),
+        }
+    }
+
+    pub fn append(&self, operation: Operation) -> Result<()> {
+        let entry = JournalEntry {
+            recorded_at: Utc::now(),
+            operation,
+        };
+        let line = serde_json::to_string(&entry).map_err(|e| JournalError(e.to_string()))?;
+
+        if let Some(dir) = self.path.parent() {
+            create_dir_all(dir).map_err(|e| JournalError(e.to_string()))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| JournalError(e.to_string()))?;
+        writeln!(file, "{}", line).map_err(|e| JournalError(e.to_string()))
+    }
+
+    /// Returns the pending entries in the order they were recorded
+    pub fn load(&self) -> Result<Vec<JournalEntry>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(v) => v,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(JournalError(e.to_string())),
+        };
+
+        content
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| serde_json::from_str(l).map_err(|e| JournalError(e.to_string())))
+            .collect()
+    }
+
+    /// Replaces the journal with the entries which are still pending, e.g. after a sync
+    pub fn replace(&self, entries: &[JournalEntry]) -> Result<()> {
+        if entries.is_empty() {
+            return match fs::remove_file(&self.path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => Err(JournalError(e.to_string())),
+                _ => Ok(()),
+            };
+        }
+
+        let mut content = String::new();
+        for entry in entries {
+            content
+                .push_str(&serde_json::to_string(entry).map_err(|e| JournalError(e.to_string()))?);
+            content.push('\n');
+        }
+        fs::write(&self.path, content).map_err(|e| JournalError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_append_load_and_replace() {
+        let dir = std::env::temp_dir().join(format!("tmlr-journal-{}", std::process::id()));
+        let journal = Journal::with_path(&dir.join(JOURNAL_FILE_NAME));
+        assert!(journal.load().unwrap().is_empty());
+
+        let started_at = Utc.ymd(2021, 6, 1).and_hms(9, 0, 0);
+        journal
+            .append(Operation::StartTracking {
+                activity_id: "1".to_owned(),
+                activity_name: "Development".to_owned(),
+                started_at,
+                note: None,
+            })
+            .unwrap();
+        journal
+            .append(Operation::StopTracking {
+                stopped_at: started_at + chrono::Duration::hours(1),
+            })
+            .unwrap();
+
+        let entries = journal.load().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(
+            &entries[0].operation,
+            Operation::StartTracking { activity_id, .. } if activity_id == "1"
+        ));
+
+        journal.replace(&entries[1..]).unwrap();
+        assert_eq!(journal.load().unwrap(), entries[1..].to_vec());
+        journal.replace(&[]).unwrap();
+        assert!(journal.load().unwrap().is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
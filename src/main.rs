@@ -1,8 +1,12 @@
 use error::Error;
 use std::{process, result::Result as StdResult};
 
+mod cache;
 mod cli;
+mod daemon;
 mod error;
+mod idle;
+mod journal;
 mod settings;
 mod timeular;
 mod util;
@@ -1,11 +1,12 @@
-use config::{Config, Environment, File};
+use config::{Config, Environment, File, FileFormat};
 use error::ConfigurationError::{self, *};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
-    fs::{create_dir_all, File as StdFile},
+    env,
+    fs::{create_dir_all, read_to_string, File as StdFile},
     io::prelude::*,
-    path::Path,
+    path::{Path, PathBuf},
     result::Result as StdResult,
 };
 
@@ -13,6 +14,16 @@ pub mod error;
 
 const DEFAULT_FILE_NAME: &str = "config.toml";
 const DEFAULT_DIR_CFG: &str = ".tmlr";
+const PROJECT_FILE_NAME: &str = ".tmlr.toml";
+const DEFAULT_DIR_CACHE: &str = "tmlr";
+const XDG_CACHE_HOME: &str = "XDG_CACHE_HOME";
+/// Settings which run commands are only read from the global config file, otherwise entering a
+/// directory with a `.tmlr.toml` would be enough to run its commands
+const GLOBAL_ONLY_KEYS: [(&str, &str); 3] = [
+    ("idle", "command"),
+    ("autotrack", "window_command"),
+    ("autotrack", "cwd_command"),
+];
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Authentication {
@@ -24,6 +35,7 @@ pub struct Authentication {
 pub struct Alias {
     pub activity: Option<HashMap<String, String>>,
     pub tag: Option<HashMap<String, String>>,
+    pub mention: Option<HashMap<String, String>>,
 }
 
 impl Alias {
@@ -68,12 +80,190 @@ impl Alias {
             }
         }
     }
+
+    pub fn add_mention_alias(&mut self, alias: String, mention_id: String) {
+        let mut mention = match &self.mention {
+            Some(v) => v.clone(),
+            None => HashMap::with_capacity(1),
+        };
+        mention.insert(alias.to_lowercase(), mention_id);
+        self.mention = Some(mention);
+    }
+
+    pub fn remove_mention_alias(&mut self, alias: &str) {
+        if let Some(a) = &self.mention {
+            let mut mention = a.clone();
+            mention.remove(&alias.to_lowercase());
+            if mention.is_empty() {
+                self.mention = None;
+            } else {
+                self.mention = Some(mention);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.activity.is_none() && self.tag.is_none() && self.mention.is_none()
+    }
+}
+
+/// Values which are used whenever a command is called without them, e.g. `tmlr start tracking`
+/// without an activity
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Defaults {
+    /// Activity id, name or alias
+    pub activity: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub mentions: Option<Vec<String>>,
+    pub space: Option<String>,
+}
+
+/// Settings of `tmlr timesheet`
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Timesheet {
+    /// Working days with less tracked time are highlighted, e.g. `8h` or `7h30m`. The targets
+    /// are used instead if they are configured.
+    pub daily_target: Option<String>,
+    /// The first day of a week, `mon` or `sun`
+    pub week_start: Option<String>,
+}
+
+/// Working-hours targets of `tmlr balance`, durations like `8h` or `7h30m`
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Targets {
+    pub mon: Option<String>,
+    pub tue: Option<String>,
+    pub wed: Option<String>,
+    pub thu: Option<String>,
+    pub fri: Option<String>,
+    pub sat: Option<String>,
+    pub sun: Option<String>,
+    /// Dates like `2021-12-24` or ranges like `2021-12-24..2021-12-26`
+    pub holidays: Option<Vec<String>>,
+    /// Path to an `.ics` file with additional holidays
+    pub holidays_file: Option<String>,
+    /// Dates or ranges of vacation days
+    pub vacation: Option<Vec<String>>,
+    /// Default start date of the balance
+    pub since: Option<String>,
+}
+
+/// Settings of `tmlr audit`
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Audit {
+    /// Working hours in which untracked gaps are reported, e.g. `09:00-17:00`
+    pub working_hours: Option<String>,
+    /// Longer time entries are reported, defaults to `10h`
+    pub max_duration: Option<String>,
+    /// Shorter gaps are ignored, defaults to `5m`
+    pub min_gap: Option<String>,
+}
+
+/// Settings of the local cache of API responses
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Cache {
+    /// Disables the cache if set to `false`
+    pub enabled: Option<bool>,
+    /// Max age of cached activities, tags, mentions and spaces, defaults to `1h`
+    pub ttl: Option<String>,
+    /// Max age of cached time entries, defaults to `5m`
+    pub time_entries_ttl: Option<String>,
+}
+
+/// Settings of `tmlr pomodoro`, the arguments take precedence
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Pomodoro {
+    /// Length of a work block, defaults to `25m`
+    pub work: Option<String>,
+    /// Length of a break, defaults to `5m`
+    #[serde(rename = "break")]
+    pub break_duration: Option<String>,
+    /// Number of work blocks, defaults to `4`
+    pub cycles: Option<u32>,
+    /// Activity tracked during breaks, breaks are not tracked if it's not set
+    pub break_activity: Option<String>,
+}
+
+/// Settings of `tmlr watch`
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Idle {
+    /// Idle time after which the idle time of the tracking is handled, defaults to `10m`
+    pub threshold: Option<String>,
+    /// Command printing the idle time in milliseconds, e.g. `xprintidle`. Without a command the
+    /// `IdleSinceHint` of logind is used. Only read from the global config file.
+    pub command: Option<String>,
+    /// `prompt` asks whether to discard the idle time, `trim` stops the tracking at the last
+    /// activity. Defaults to `prompt`.
+    pub action: Option<String>,
+    /// How often the idle time is checked, defaults to `30s`
+    pub interval: Option<String>,
+}
+
+/// Maps branches to the activity which is tracked while they are checked out
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BranchMapping {
+    /// Glob pattern of the branch name, e.g. `PROJ-*`
+    pub pattern: String,
+    /// Activity id, name or alias
+    pub activity: String,
+    /// Mention which is added to the note of the tracking
+    pub mention: Option<String>,
+}
+
+/// Settings of the git hooks installed by `tmlr git install-hooks`
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Git {
+    /// Appends the time tracked since the last commit to commit messages
+    pub append_time: Option<bool>,
+    /// The first matching mapping is taken
+    pub branches: Option<Vec<BranchMapping>>,
+}
+
+/// Selects an activity by the environment, all given conditions have to match
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct AutotrackRule {
+    /// Glob of the working directory or one of its parents, e.g. `~/work/acme/*`
+    pub cwd: Option<String>,
+    /// Glob of a git remote URL of the working directory, e.g. `*github.com?acme/*`
+    pub remote: Option<String>,
+    /// Glob of the output of the window command, e.g. `*Slack*`
+    pub window: Option<String>,
+    /// Activity id, name or alias
+    pub activity: String,
+    pub tags: Option<Vec<String>>,
+    pub note: Option<String>,
+}
+
+/// Settings of `tmlr autotrack`
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Autotrack {
+    /// Command printing the title of the focused window, e.g.
+    /// `xdotool getactivewindow getwindowname`. Only read from the global config file.
+    pub window_command: Option<String>,
+    /// Command printing the working directory of the focused window, e.g. `xcwd`. Defaults to
+    /// the directory `tmlr autotrack` runs in. Only read from the global config file.
+    pub cwd_command: Option<String>,
+    /// How often the rules are evaluated, defaults to `30s`
+    pub interval: Option<String>,
+    /// How long a new match has to last before the tracking is switched, defaults to `2m`
+    pub min_dwell: Option<String>,
+    /// The first matching rule is taken
+    pub rules: Option<Vec<AutotrackRule>>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Settings {
     pub auth: Option<Authentication>,
     pub alias: Option<Alias>,
+    pub defaults: Option<Defaults>,
+    pub timesheet: Option<Timesheet>,
+    pub targets: Option<Targets>,
+    pub audit: Option<Audit>,
+    pub cache: Option<Cache>,
+    pub pomodoro: Option<Pomodoro>,
+    pub idle: Option<Idle>,
+    pub git: Option<Git>,
+    pub autotrack: Option<Autotrack>,
 }
 
 impl Default for Settings {
@@ -81,12 +271,41 @@ impl Default for Settings {
         Settings {
             auth: None,
             alias: None,
+            defaults: None,
+            timesheet: None,
+            targets: None,
+            audit: None,
+            cache: None,
+            pomodoro: None,
+            idle: None,
+            git: None,
+            autotrack: None,
         }
     }
 }
 
 impl Settings {
+    /// Loads the settings with the following precedence (highest first):
+    /// 1. Environment variables (`TMLR_...`)
+    /// 2. The nearest `.tmlr.toml` found by walking up from the current directory
+    /// 3. The global config file (default `~/.tmlr/config.toml`)
     pub fn new(custom_path: Option<&str>) -> StdResult<Self, ConfigurationError> {
+        let project_file = env::current_dir()
+            .ok()
+            .and_then(|dir| find_project_file(&dir));
+        Settings::load(custom_path, project_file.as_deref())
+    }
+
+    /// Loads the settings without a project file, which is needed whenever the settings are
+    /// written back to the global config file
+    pub fn new_global(custom_path: Option<&str>) -> StdResult<Self, ConfigurationError> {
+        Settings::load(custom_path, None)
+    }
+
+    fn load(
+        custom_path: Option<&str>,
+        project_file: Option<&Path>,
+    ) -> StdResult<Self, ConfigurationError> {
         let mut s = Config::new();
 
         let path = match custom_path {
@@ -95,12 +314,21 @@ impl Settings {
         };
 
         log::debug!("Trying to load settings from path {}", path);
-        if !Path::new(&path).exists() {
+        let global_exists = Path::new(&path).exists();
+        if !global_exists && project_file.is_none() {
             return Err(FileNotFoundError(path.to_owned()));
         }
 
-        s.merge(File::with_name(&path))
-            .map_err(|e| MessageError(e.to_string()))?;
+        if global_exists {
+            s.merge(File::with_name(&path))
+                .map_err(|e| MessageError(e.to_string()))?;
+        }
+
+        if let Some(p) = project_file {
+            log::debug!("Merging project settings from path {}", p.display());
+            s.merge(File::from_str(&read_project_file(p)?, FileFormat::Toml))
+                .map_err(|e| MessageError(e.to_string()))?;
+        }
 
         // This makes it so "TMLR_AUTH__API_SECRET overrides the auth.api_secret key for example
         // the __ is used, because variables might be named some_var
@@ -132,6 +360,7 @@ impl Settings {
         let alias_obj = self.alias.get_or_insert(Alias {
             activity: None,
             tag: None,
+            mention: None,
         });
 
         alias_obj.add_activity_alias(alias, activity_id);
@@ -141,15 +370,26 @@ impl Settings {
         let alias_obj = self.alias.get_or_insert(Alias {
             activity: None,
             tag: None,
+            mention: None,
         });
 
         alias_obj.add_tag_alias(alias, tag_id);
     }
 
+    pub fn add_mention_alias(&mut self, alias: String, mention_id: String) {
+        let alias_obj = self.alias.get_or_insert(Alias {
+            activity: None,
+            tag: None,
+            mention: None,
+        });
+
+        alias_obj.add_mention_alias(alias, mention_id);
+    }
+
     pub fn remove_activity_alias(&mut self, alias: &str) {
         if let Some(mut alias_obj) = self.alias.clone() {
             alias_obj.remove_activity_alias(alias);
-            if alias_obj.tag.is_none() && alias_obj.activity.is_none() {
+            if alias_obj.is_empty() {
                 self.alias = None
             } else {
                 self.alias = Some(alias_obj)
@@ -160,22 +400,98 @@ impl Settings {
     pub fn remove_tag_alias(&mut self, alias: &str) {
         if let Some(mut alias_obj) = self.alias.clone() {
             alias_obj.remove_tag_alias(alias);
-            if alias_obj.tag.is_none() && alias_obj.activity.is_none() {
+            if alias_obj.is_empty() {
+                self.alias = None
+            } else {
+                self.alias = Some(alias_obj)
+            }
+        }
+    }
+
+    pub fn remove_mention_alias(&mut self, alias: &str) {
+        if let Some(mut alias_obj) = self.alias.clone() {
+            alias_obj.remove_mention_alias(alias);
+            if alias_obj.is_empty() {
                 self.alias = None
             } else {
                 self.alias = Some(alias_obj)
             }
         }
     }
+
+    pub fn resolve_activity_alias(&self, alias: &str) -> Option<String> {
+        self.alias
+            .as_ref()
+            .and_then(|a| a.activity.as_ref())
+            .and_then(|a| a.get(&alias.to_lowercase()))
+            .cloned()
+    }
+
+    pub fn resolve_tag_alias(&self, alias: &str) -> Option<String> {
+        self.alias
+            .as_ref()
+            .and_then(|a| a.tag.as_ref())
+            .and_then(|a| a.get(&alias.to_lowercase()))
+            .cloned()
+    }
+
+    pub fn resolve_mention_alias(&self, alias: &str) -> Option<String> {
+        self.alias
+            .as_ref()
+            .and_then(|a| a.mention.as_ref())
+            .and_then(|a| a.get(&alias.to_lowercase()))
+            .cloned()
+    }
+}
+
+/// Reads the project file without the keys which are only allowed in the global config file
+fn read_project_file(path: &Path) -> StdResult<String, ConfigurationError> {
+    let content = read_to_string(path).map_err(|e| MessageError(e.to_string()))?;
+    let mut value: toml::Value =
+        toml::from_str(&content).map_err(|e| MessageError(e.to_string()))?;
+    for (section, key) in GLOBAL_ONLY_KEYS.iter() {
+        let removed = value
+            .get_mut(section)
+            .and_then(|v| v.as_table_mut())
+            .and_then(|t| t.remove(*key));
+        if removed.is_some() {
+            log::warn!(
+                "{}.{} is ignored in {}, it's only read from the global settings.",
+                section,
+                key,
+                path.display()
+            );
+        }
+    }
+    Ok(toml::to_string(&value)?)
+}
+
+/// Walks up from the given directory and returns the first `.tmlr.toml` found
+pub fn find_project_file(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_FILE_NAME))
+        .find(|file| file.is_file())
 }
 
-fn get_default_cfg_dir() -> String {
+pub fn get_default_cfg_dir() -> String {
     match home::home_dir() {
         Some(path) => format!("{}/{}", path.display(), DEFAULT_DIR_CFG),
         None => DEFAULT_DIR_CFG.to_owned(),
     }
 }
 
+/// The cache dir is `$XDG_CACHE_HOME/tmlr`, by default `~/.cache/tmlr`
+pub fn get_default_cache_dir() -> String {
+    match env::var(XDG_CACHE_HOME).ok().filter(|v| !v.is_empty()) {
+        Some(v) => format!("{}/{}", v, DEFAULT_DIR_CACHE),
+        None => match home::home_dir() {
+            Some(path) => format!("{}/.cache/{}", path.display(), DEFAULT_DIR_CACHE),
+            None => DEFAULT_DIR_CFG.to_owned(),
+        },
+    }
+}
+
 fn get_default_file_path() -> String {
     format!("{}/{}", get_default_cfg_dir(), DEFAULT_FILE_NAME)
 }
@@ -189,6 +505,7 @@ mod tests {
         let mut alias = Alias {
             activity: None,
             tag: None,
+            mention: None,
         };
 
         alias.add_activity_alias("a".to_owned(), "Some Activity".to_owned());
@@ -198,7 +515,8 @@ mod tests {
             alias,
             Alias {
                 activity: Some(ref result),
-                tag: None
+                tag: None,
+                mention: None
             }
         ));
 
@@ -208,7 +526,8 @@ mod tests {
             alias,
             Alias {
                 activity: Some(result),
-                tag: None
+                tag: None,
+                mention: None
             }
         ));
     }
@@ -218,6 +537,7 @@ mod tests {
         let mut alias = Alias {
             activity: None,
             tag: None,
+            mention: None,
         };
 
         alias.add_tag_alias("x".to_owned(), "Tag x".to_owned());
@@ -227,7 +547,8 @@ mod tests {
             alias,
             Alias {
                 activity: None,
-                tag: Some(ref result)
+                tag: Some(ref result),
+                mention: None
             }
         ));
 
@@ -237,7 +558,8 @@ mod tests {
             alias,
             Alias {
                 activity: None,
-                tag: Some(result)
+                tag: Some(result),
+                mention: None
             }
         ));
     }
@@ -251,6 +573,7 @@ mod tests {
         let mut alias = Alias {
             activity: None,
             tag: Some(tag_map),
+            mention: None,
         };
 
         alias.remove_tag_alias("x");
@@ -261,7 +584,8 @@ mod tests {
             alias,
             Alias {
                 activity: None,
-                tag: Some(ref result)
+                tag: Some(ref result),
+                mention: None
             }
         ));
 
@@ -270,7 +594,8 @@ mod tests {
             alias,
             Alias {
                 activity: None,
-                tag: None
+                tag: None,
+                mention: None
             }
         ));
     }
@@ -284,6 +609,7 @@ mod tests {
         let mut alias = Alias {
             activity: Some(activity_map),
             tag: None,
+            mention: None,
         };
 
         alias.remove_activity_alias("b");
@@ -294,7 +620,8 @@ mod tests {
             alias,
             Alias {
                 activity: Some(ref result),
-                tag: None
+                tag: None,
+                mention: None
             }
         ));
 
@@ -303,7 +630,8 @@ mod tests {
             alias,
             Alias {
                 activity: None,
-                tag: None
+                tag: None,
+                mention: None
             }
         ));
     }
@@ -313,6 +641,7 @@ mod tests {
         let mut settings = Settings {
             auth: None,
             alias: None,
+            ..Settings::default()
         };
 
         settings.add_activity_alias("a".to_owned(), "Activity A".to_owned());
@@ -324,8 +653,10 @@ mod tests {
                 auth: None,
                 alias: Some(Alias {
                     activity: Some(ref result),
-                    tag: None
-                })
+                    tag: None,
+                    mention: None
+                }),
+                ..
             }
         ));
 
@@ -337,8 +668,10 @@ mod tests {
                 auth: None,
                 alias: Some(Alias {
                     activity: Some(ref result),
-                    tag: None
-                })
+                    tag: None,
+                    mention: None
+                }),
+                ..
             }
         ));
     }
@@ -348,6 +681,7 @@ mod tests {
         let mut settings = Settings {
             auth: None,
             alias: None,
+            ..Settings::default()
         };
 
         settings.add_tag_alias("x".to_owned(), "Tag X".to_owned());
@@ -359,8 +693,10 @@ mod tests {
                 auth: None,
                 alias: Some(Alias {
                     activity: None,
-                    tag: Some(ref result)
-                })
+                    tag: Some(ref result),
+                    mention: None
+                }),
+                ..
             }
         ));
 
@@ -372,8 +708,10 @@ mod tests {
                 auth: None,
                 alias: Some(Alias {
                     activity: None,
-                    tag: Some(ref result)
-                })
+                    tag: Some(ref result),
+                    mention: None
+                }),
+                ..
             }
         ));
     }
@@ -389,7 +727,9 @@ mod tests {
             alias: Some(Alias {
                 activity: None,
                 tag: Some(tag_map),
+                mention: None,
             }),
+            ..Settings::default()
         };
 
         settings.remove_tag_alias("x");
@@ -402,8 +742,10 @@ mod tests {
                 auth: None,
                 alias: Some(Alias{
                     activity: None,
-                    tag: Some(ref result)
-                })
+                    tag: Some(ref result),
+                    mention: None
+                }),
+                ..
             }
         ));
 
@@ -412,7 +754,8 @@ mod tests {
             settings,
             Settings {
                 auth: None,
-                alias: None
+                alias: None,
+                ..
             }
         ));
     }
@@ -428,7 +771,9 @@ mod tests {
             alias: Some(Alias {
                 activity: Some(activity_map),
                 tag: None,
+                mention: None,
             }),
+            ..Settings::default()
         };
 
         settings.remove_activity_alias("b");
@@ -441,8 +786,10 @@ mod tests {
                 auth: None,
                 alias: Some(Alias {
                     activity: Some(ref result),
-                    tag: None
-                })
+                    tag: None,
+                    mention: None
+                }),
+                ..
             }
         ));
 
@@ -451,8 +798,107 @@ mod tests {
             settings,
             Settings {
                 auth: None,
-                alias: None
+                alias: None,
+                ..
             }
         ));
     }
+
+    fn create_temp_dir() -> PathBuf {
+        let dir = env::temp_dir().join(format!("tmlr-test-{}", rand::random::<u64>()));
+        create_dir_all(&dir).expect("temp dir can be created");
+        dir
+    }
+
+    #[test]
+    fn test_find_project_file() {
+        let root = create_temp_dir();
+        let nested = root.join("a").join("b");
+        create_dir_all(&nested).expect("nested dir can be created");
+
+        assert_eq!(find_project_file(&nested), None);
+
+        std::fs::write(root.join(PROJECT_FILE_NAME), "").expect("project file can be written");
+        assert_eq!(
+            find_project_file(&nested),
+            Some(root.join(PROJECT_FILE_NAME))
+        );
+
+        std::fs::write(nested.join(PROJECT_FILE_NAME), "").expect("project file can be written");
+        assert_eq!(
+            find_project_file(&nested),
+            Some(nested.join(PROJECT_FILE_NAME))
+        );
+
+        std::fs::remove_dir_all(root).expect("temp dir can be removed");
+    }
+
+    #[test]
+    fn test_settings_load_project_file() {
+        let root = create_temp_dir();
+        let global = root.join(DEFAULT_FILE_NAME);
+        let project = root.join(PROJECT_FILE_NAME);
+        std::fs::write(
+            &global,
+            "[auth]\napi_key = \"key\"\n\n[defaults]\nactivity = \"global\"\nspace = \"1\"\n",
+        )
+        .expect("global file can be written");
+        std::fs::write(
+            &project,
+            "[defaults]\nactivity = \"project\"\ntags = [\"bug\"]\n",
+        )
+        .expect("project file can be written");
+
+        let global_path = global.to_str();
+        let settings = Settings::load(global_path, Some(&project)).expect("settings can be loaded");
+        let defaults = settings.defaults.expect("defaults are set");
+        assert_eq!(defaults.activity, Some("project".to_owned()));
+        assert_eq!(defaults.tags, Some(vec!["bug".to_owned()]));
+        assert_eq!(defaults.space, Some("1".to_owned()));
+        assert_eq!(
+            settings.auth.and_then(|a| a.api_key),
+            Some("key".to_owned())
+        );
+
+        let settings = Settings::new_global(global_path).expect("settings can be loaded");
+        assert_eq!(
+            settings.defaults.and_then(|d| d.activity),
+            Some("global".to_owned())
+        );
+
+        std::fs::remove_dir_all(root).expect("temp dir can be removed");
+    }
+
+    #[test]
+    fn test_settings_load_project_file_without_commands() {
+        let root = create_temp_dir();
+        let global = root.join(DEFAULT_FILE_NAME);
+        let project = root.join(PROJECT_FILE_NAME);
+        std::fs::write(&global, "[idle]\ncommand = \"xprintidle\"\n")
+            .expect("global file can be written");
+        std::fs::write(
+            &project,
+            "[idle]\ncommand = \"echo project\"\nthreshold = \"5m\"\n\n\
+             [autotrack]\nwindow_command = \"echo project\"\ncwd_command = \"echo project\"\n\
+             min_dwell = \"1m\"\n",
+        )
+        .expect("project file can be written");
+
+        let settings =
+            Settings::load(global.to_str(), Some(&project)).expect("settings can be loaded");
+        let idle = settings.idle.expect("idle is set");
+        assert_eq!(idle.command, Some("xprintidle".to_owned()));
+        assert_eq!(idle.threshold, Some("5m".to_owned()));
+        let autotrack = settings.autotrack.expect("autotrack is set");
+        assert_eq!(autotrack.window_command, None);
+        assert_eq!(autotrack.cwd_command, None);
+        assert_eq!(autotrack.min_dwell, Some("1m".to_owned()));
+
+        std::fs::remove_file(&global).expect("global file can be removed");
+        let settings =
+            Settings::load(global.to_str(), Some(&project)).expect("settings can be loaded");
+        assert_eq!(settings.idle.and_then(|i| i.command), None);
+
+        std::fs::remove_dir_all(root).expect("temp dir can be removed");
+    }
 }
@@ -1,5 +1,5 @@
 use super::{
-    data::{ActivityRequest, ActivityResponse},
+    data::{ActivityListResponse, ActivityRequest, ActivityResponse, EditActivityRequest},
     TimeularHttpClient,
 };
 use crate::Result;
@@ -7,6 +7,10 @@ use crate::Result;
 const DEFAULT_INTEGRATION: &str = "zei";
 
 impl TimeularHttpClient<'_> {
+    pub fn list_activities(&self, token: String) -> Result<ActivityListResponse> {
+        self.get(token, "/activities", "fetching activities".to_owned())
+    }
+
     pub fn create_activity(
         &self,
         token: String,
@@ -26,4 +30,61 @@ impl TimeularHttpClient<'_> {
             "creating an activity".to_owned(),
         )
     }
+
+    pub fn edit_activity(
+        &self,
+        token: String,
+        activity_id: &str,
+        name: Option<String>,
+        color: Option<String>,
+    ) -> Result<ActivityResponse> {
+        self.patch(
+            &format!("/activities/{}", activity_id),
+            token,
+            &EditActivityRequest { name, color },
+            "editing an activity".to_owned(),
+        )
+    }
+
+    /// Archives an activity, archived activities can't be tracked anymore but keep their time
+    /// entries
+    pub fn archive_activity(&self, token: String, activity_id: &str) -> Result<()> {
+        self.delete_without_body(&format!("/activities/{}", activity_id), token)
+    }
+
+    pub fn unarchive_activity(&self, token: String, activity_id: &str) -> Result<ActivityResponse> {
+        self.post(
+            &format!("/activities/{}/unarchive", activity_id),
+            token,
+            serde_json::json!({}),
+            "unarchiving an activity".to_owned(),
+        )
+    }
+
+    pub fn assign_device_side(
+        &self,
+        token: String,
+        activity_id: &str,
+        side: u8,
+    ) -> Result<ActivityResponse> {
+        self.post(
+            &format!("/activities/{}/device-side/{}", activity_id, side),
+            token,
+            serde_json::json!({}),
+            "assigning an activity to a device side".to_owned(),
+        )
+    }
+
+    pub fn unassign_device_side(
+        &self,
+        token: String,
+        activity_id: &str,
+        side: u8,
+    ) -> Result<ActivityResponse> {
+        self.delete(
+            &format!("/activities/{}/device-side/{}", activity_id, side),
+            token,
+            "unassigning an activity from a device side".to_owned(),
+        )
+    }
 }
@@ -1,8 +1,5 @@
 use super::{data::LoginRequest, data::LoginResponse, TimeularHttpClient};
-use crate::{
-    error::Error::{ParseJsonError, TimeularApiError},
-    Result,
-};
+use crate::{error::Error::ParseJsonError, Result};
 
 impl TimeularHttpClient<'_> {
     pub fn login(&self, api_key: String, api_secret: String) -> Result<String> {
@@ -16,7 +13,7 @@ impl TimeularHttpClient<'_> {
                 api_secret,
             })
             .send()
-            .map_err(|e| TimeularApiError(url.to_owned(), e.to_string()))?;
+            .map_err(|e| TimeularHttpClient::create_send_error(&url, e))?;
 
         if !resp.status().is_success() {
             return Err(TimeularHttpClient::create_default_error(
@@ -38,7 +35,7 @@ impl TimeularHttpClient<'_> {
             .post(url.to_owned())
             .bearer_auth(token)
             .send()
-            .map_err(|e| TimeularApiError(url.to_owned(), e.to_string()))?;
+            .map_err(|e| TimeularHttpClient::create_send_error(&url, e))?;
 
         Ok(())
     }
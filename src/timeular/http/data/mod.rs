@@ -1,7 +1,12 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+pub mod note;
 pub mod space;
+pub mod time_entry;
+pub mod tracking;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f";
 
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -24,7 +29,24 @@ pub struct ActivityRequest {
     pub space_id: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EditActivityRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityListResponse {
+    pub activities: Vec<ActivityResponse>,
+    pub inactive_activities: Vec<ActivityResponse>,
+    pub archived_activities: Vec<ActivityResponse>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ActivityResponse {
     pub id: String,
@@ -32,6 +54,9 @@ pub struct ActivityResponse {
     pub color: String,
     pub integration: String,
     pub space_id: String,
+    /// The side of the tracker the activity is assigned to
+    #[serde(default)]
+    pub device_side: Option<u8>,
 }
 
 #[derive(Serialize, Debug)]
@@ -43,12 +68,55 @@ pub struct TagRequest {
     pub space_id: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EditTagRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct TagResponse {
     pub id: i64,
     pub key: String,
     pub label: String,
+    #[serde(default)]
     pub scope: String,
+    #[serde(default)]
     pub space_id: String,
 }
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct TagsAndMentionsResponse {
+    pub tags: Vec<TagResponse>,
+    pub mentions: Vec<TagResponse>,
+}
+
+/// Timeular expects timestamps in UTC without any offset information, e.g. `2021-06-01T08:30:00.000`
+pub mod timestamp {
+    use super::*;
+
+    pub fn format(date: &DateTime<Utc>) -> String {
+        date.format(TIMESTAMP_FORMAT).to_string()
+    }
+
+    pub fn serialize<S>(date: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format(date))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        NaiveDateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M:%S%.f")
+            .map(|v| DateTime::from_utc(v, Utc))
+            .map_err(serde::de::Error::custom)
+    }
+}
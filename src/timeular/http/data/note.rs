@@ -0,0 +1,165 @@
+use super::TagResponse;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Note {
+    pub text: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<NoteReference>,
+    #[serde(default)]
+    pub mentions: Vec<NoteReference>,
+}
+
+/// References a tag or mention inside the note text, `indices` marks the start and end of the
+/// label in the text (counted in characters)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct NoteReference {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub id: Option<i64>,
+    pub key: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub label: Option<String>,
+    pub indices: [usize; 2],
+}
+
+impl Note {
+    pub fn is_empty(&self) -> bool {
+        self.text.as_ref().map(|v| v.is_empty()).unwrap_or(true)
+            && self.tags.is_empty()
+            && self.mentions.is_empty()
+    }
+}
+
+impl Note {
+    /// Builds a note where the given tags and mentions are appended as `#label` and `@label`
+    /// tokens after the text
+    pub fn with_references(
+        text: Option<String>,
+        tags: &[TagResponse],
+        mentions: &[TagResponse],
+    ) -> Self {
+        let mut result = text.unwrap_or_default();
+        let mut note_tags = Vec::with_capacity(tags.len());
+        let mut note_mentions = Vec::with_capacity(mentions.len());
+
+        for (prefix, entries, refs) in [
+            ("#", tags, &mut note_tags),
+            ("@", mentions, &mut note_mentions),
+        ] {
+            for e in entries {
+                if !result.is_empty() {
+                    result.push(' ');
+                }
+                let start = result.chars().count();
+                result.push_str(prefix);
+                result.push_str(&e.label);
+                refs.push(NoteReference {
+                    id: Some(e.id),
+                    key: e.key.to_owned(),
+                    label: Some(e.label.to_owned()),
+                    indices: [start, result.chars().count()],
+                });
+            }
+        }
+
+        Note {
+            text: if result.is_empty() {
+                None
+            } else {
+                Some(result)
+            },
+            tags: note_tags,
+            mentions: note_mentions,
+        }
+    }
+}
+
+impl Note {
+    /// Appends the other note separated by a space, the indices of its references are moved
+    /// accordingly
+    pub fn merge(self, other: Note) -> Self {
+        if other.is_empty() {
+            return self;
+        }
+        if self.is_empty() {
+            return other;
+        }
+
+        let mut text = self.text.unwrap_or_default();
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        let offset = text.chars().count();
+        text.push_str(&other.text.unwrap_or_default());
+
+        let shift = |refs: Vec<NoteReference>| {
+            refs.into_iter().map(move |mut r| {
+                r.indices = [r.indices[0] + offset, r.indices[1] + offset];
+                r
+            })
+        };
+
+        let mut tags = self.tags;
+        tags.extend(shift(other.tags));
+        let mut mentions = self.mentions;
+        mentions.extend(shift(other.mentions));
+
+        Note {
+            text: Some(text),
+            tags,
+            mentions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(id: i64, label: &str) -> TagResponse {
+        TagResponse {
+            id,
+            key: format!("key-{}", id),
+            label: label.to_owned(),
+            scope: "timeular".to_owned(),
+            space_id: "1".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_note_with_references() {
+        let note = Note::with_references(None, &[], &[]);
+        assert!(note.is_empty());
+
+        let note = Note::with_references(
+            Some("fixing".to_owned()),
+            &[tag(1, "bug"), tag(2, "urgent")],
+            &[tag(3, "acme")],
+        );
+        assert_eq!(note.text, Some("fixing #bug #urgent @acme".to_owned()));
+        assert_eq!(note.tags.len(), 2);
+        assert_eq!(note.tags[0].indices, [7, 11]);
+        assert_eq!(note.tags[0].key, "key-1");
+        assert_eq!(note.tags[1].indices, [12, 19]);
+        assert_eq!(note.mentions.len(), 1);
+        assert_eq!(note.mentions[0].indices, [20, 25]);
+        assert_eq!(note.mentions[0].id, Some(3));
+    }
+
+    #[test]
+    fn test_note_merge() {
+        let first = Note::with_references(Some("fixing".to_owned()), &[tag(1, "bug")], &[]);
+        let second = Note::with_references(Some("call".to_owned()), &[], &[tag(3, "acme")]);
+
+        let merged = first.clone().merge(Note::default());
+        assert_eq!(merged.text, first.text);
+
+        let merged = Note::default().merge(second.clone());
+        assert_eq!(merged.text, second.text);
+
+        let merged = first.merge(second);
+        assert_eq!(merged.text, Some("fixing #bug call @acme".to_owned()));
+        assert_eq!(merged.tags[0].indices, [7, 11]);
+        assert_eq!(merged.mentions[0].indices, [17, 22]);
+    }
+}
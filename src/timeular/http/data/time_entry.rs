@@ -0,0 +1,64 @@
+use super::{note::Note, timestamp};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeEntryListResponse {
+    pub time_entries: Vec<TimeEntryResponse>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeEntryRequest {
+    pub activity_id: String,
+    #[serde(with = "timestamp")]
+    pub started_at: DateTime<Utc>,
+    #[serde(with = "timestamp")]
+    pub stopped_at: DateTime<Utc>,
+    pub note: Note,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeEntryResponse {
+    pub id: String,
+    pub activity_id: String,
+    pub duration: DurationResponse,
+    pub note: Option<Note>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DurationResponse {
+    #[serde(with = "timestamp")]
+    pub started_at: DateTime<Utc>,
+    #[serde(with = "timestamp")]
+    pub stopped_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+impl TimeEntryResponse {
+    /// Creates a time entry without a note
+    pub fn new(
+        id: &str,
+        activity_id: &str,
+        started_at: DateTime<Utc>,
+        stopped_at: DateTime<Utc>,
+    ) -> Self {
+        TimeEntryResponse {
+            id: id.to_owned(),
+            activity_id: activity_id.to_owned(),
+            duration: DurationResponse {
+                started_at,
+                stopped_at,
+            },
+            note: None,
+        }
+    }
+
+    pub fn with_note(mut self, note: Note) -> Self {
+        self.note = Some(note);
+        self
+    }
+}
@@ -0,0 +1,44 @@
+use super::{note::Note, time_entry::TimeEntryResponse, timestamp};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackingResponse {
+    pub current_tracking: Option<CurrentTrackingResponse>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentTrackingResponse {
+    pub activity_id: String,
+    #[serde(with = "timestamp")]
+    pub started_at: DateTime<Utc>,
+    pub note: Option<Note>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StartTrackingRequest {
+    #[serde(with = "timestamp")]
+    pub started_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EditTrackingRequest {
+    pub note: Note,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StopTrackingRequest {
+    #[serde(with = "timestamp")]
+    pub stopped_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StopTrackingResponse {
+    pub created_time_entry: TimeEntryResponse,
+}
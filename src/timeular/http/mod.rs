@@ -1,5 +1,5 @@
 use crate::{
-    error::Error::{self, ParseJsonError, TimeularApiError},
+    error::Error::{self, ParseJsonError},
     Result,
 };
 use reqwest::{
@@ -11,9 +11,11 @@ use std::time::Duration;
 
 pub mod activity;
 pub mod auth;
-mod data;
+pub mod data;
 pub mod space;
+pub mod time_entry;
 pub mod tnm;
+pub mod tracking;
 
 #[derive(Clone)]
 pub struct TimeularHttpClient<'a> {
@@ -64,7 +66,41 @@ impl TimeularHttpClient<'_> {
             .headers(TimeularHttpClient::construct_headers(Some(&token)))
             .json(&data)
             .send()
-            .map_err(|e| TimeularApiError(url.to_owned(), e.to_string()))?;
+            .map_err(|e| TimeularHttpClient::create_send_error(&url, e))?;
+
+        if !resp.status().is_success() {
+            return Err(TimeularHttpClient::create_default_error(
+                url.to_owned(),
+                resp,
+            ));
+        }
+
+        let result: T = resp.json().map_err(|e| {
+            log::debug!("{:?}", e);
+            ParseJsonError(parse_msg)
+        })?;
+        Ok(result)
+    }
+
+    fn patch<T>(
+        &self,
+        uri: &str,
+        token: String,
+        data: impl Serialize,
+        parse_msg: String,
+    ) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let url = self.uri(uri);
+
+        let resp = self
+            .client
+            .patch(url.to_owned())
+            .headers(TimeularHttpClient::construct_headers(Some(&token)))
+            .json(&data)
+            .send()
+            .map_err(|e| TimeularHttpClient::create_send_error(&url, e))?;
 
         if !resp.status().is_success() {
             return Err(TimeularHttpClient::create_default_error(
@@ -91,7 +127,7 @@ impl TimeularHttpClient<'_> {
             .get(url.to_owned())
             .bearer_auth(token)
             .send()
-            .map_err(|e| TimeularApiError(url.to_owned(), e.to_string()))?;
+            .map_err(|e| TimeularHttpClient::create_send_error(&url, e))?;
 
         if !resp.status().is_success() {
             return Err(TimeularHttpClient::create_default_error(
@@ -107,6 +143,42 @@ impl TimeularHttpClient<'_> {
         Ok(result)
     }
 
+    fn delete<T>(&self, uri: &str, token: String, parse_msg: String) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let resp = self.send_delete(uri, token)?;
+        let result: T = resp.json().map_err(|e| {
+            log::debug!("{:?}", e);
+            ParseJsonError(parse_msg)
+        })?;
+        Ok(result)
+    }
+
+    /// Deletes without reading the response, which may be empty
+    fn delete_without_body(&self, uri: &str, token: String) -> Result<()> {
+        self.send_delete(uri, token).map(|_| ())
+    }
+
+    fn send_delete(&self, uri: &str, token: String) -> Result<Response> {
+        let url = self.uri(uri);
+
+        let resp = self
+            .client
+            .delete(url.to_owned())
+            .bearer_auth(token)
+            .send()
+            .map_err(|e| TimeularHttpClient::create_send_error(&url, e))?;
+
+        if !resp.status().is_success() {
+            return Err(TimeularHttpClient::create_default_error(
+                url.to_owned(),
+                resp,
+            ));
+        }
+        Ok(resp)
+    }
+
     fn construct_headers(token: Option<&str>) -> HeaderMap {
         let mut headers = HeaderMap::with_capacity(2);
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
@@ -130,4 +202,14 @@ impl TimeularHttpClient<'_> {
             ),
         )
     }
+
+    /// Connection failures and timeouts are reported as unreachable API, so commands can fall
+    /// back to the offline journal
+    fn create_send_error(url: &str, err: reqwest::Error) -> Error {
+        if err.is_connect() || err.is_timeout() {
+            Error::ApiUnreachableError(err.to_string())
+        } else {
+            Error::TimeularApiError(url.to_owned(), err.to_string())
+        }
+    }
 }
@@ -0,0 +1,85 @@
+use super::{
+    data::{
+        note::Note,
+        time_entry::{TimeEntryListResponse, TimeEntryRequest, TimeEntryResponse},
+        timestamp,
+    },
+    TimeularHttpClient,
+};
+use crate::Result;
+use chrono::{DateTime, Utc};
+
+impl TimeularHttpClient<'_> {
+    /// Fetches all time entries which overlap with the given range
+    pub fn list_time_entries(
+        &self,
+        token: String,
+        stopped_after: DateTime<Utc>,
+        started_before: DateTime<Utc>,
+    ) -> Result<TimeEntryListResponse> {
+        self.get(
+            token,
+            &format!(
+                "/time-entries/{}/{}",
+                timestamp::format(&stopped_after),
+                timestamp::format(&started_before)
+            ),
+            "fetching time entries".to_owned(),
+        )
+    }
+
+    pub fn create_time_entry(
+        &self,
+        token: String,
+        activity_id: String,
+        started_at: DateTime<Utc>,
+        stopped_at: DateTime<Utc>,
+        note: Note,
+    ) -> Result<TimeEntryResponse> {
+        self.post(
+            "/time-entries",
+            token,
+            &TimeEntryRequest {
+                activity_id,
+                started_at,
+                stopped_at,
+                note,
+            },
+            "creating a time entry".to_owned(),
+        )
+    }
+
+    pub fn get_time_entry(&self, token: String, time_entry_id: &str) -> Result<TimeEntryResponse> {
+        self.get(
+            token,
+            &format!("/time-entries/{}", time_entry_id),
+            "fetching a time entry".to_owned(),
+        )
+    }
+
+    pub fn edit_time_entry(
+        &self,
+        token: String,
+        time_entry_id: &str,
+        activity_id: String,
+        started_at: DateTime<Utc>,
+        stopped_at: DateTime<Utc>,
+        note: Note,
+    ) -> Result<TimeEntryResponse> {
+        self.patch(
+            &format!("/time-entries/{}", time_entry_id),
+            token,
+            &TimeEntryRequest {
+                activity_id,
+                started_at,
+                stopped_at,
+                note,
+            },
+            "editing a time entry".to_owned(),
+        )
+    }
+
+    pub fn delete_time_entry(&self, token: String, time_entry_id: &str) -> Result<()> {
+        self.delete_without_body(&format!("/time-entries/{}", time_entry_id), token)
+    }
+}
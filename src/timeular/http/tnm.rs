@@ -1,5 +1,5 @@
 use super::{
-    data::{TagRequest, TagResponse},
+    data::{EditTagRequest, TagRequest, TagResponse, TagsAndMentionsResponse},
     TimeularHttpClient,
 };
 use crate::Result;
@@ -7,6 +7,14 @@ use crate::Result;
 const DEFAULT_SCOPE: &str = "timeular";
 
 impl TimeularHttpClient<'_> {
+    pub fn list_tags_and_mentions(&self, token: String) -> Result<TagsAndMentionsResponse> {
+        self.get(
+            token,
+            "/tags-and-mentions",
+            "fetching tags and mentions".to_owned(),
+        )
+    }
+
     pub fn create_tag(
         &self,
         token: String,
@@ -26,4 +34,34 @@ impl TimeularHttpClient<'_> {
             "creating a tag".to_owned(),
         )
     }
+
+    pub fn edit_tag(
+        &self,
+        token: String,
+        tag_id: i64,
+        label: Option<String>,
+        key: Option<String>,
+    ) -> Result<TagResponse> {
+        self.patch(
+            &format!("/tags/{}", tag_id),
+            token,
+            &EditTagRequest { label, key },
+            "editing a tag".to_owned(),
+        )
+    }
+
+    pub fn edit_mention(
+        &self,
+        token: String,
+        mention_id: i64,
+        label: Option<String>,
+        key: Option<String>,
+    ) -> Result<TagResponse> {
+        self.patch(
+            &format!("/mentions/{}", mention_id),
+            token,
+            &EditTagRequest { label, key },
+            "editing a mention".to_owned(),
+        )
+    }
 }
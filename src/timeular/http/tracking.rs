@@ -0,0 +1,58 @@
+use super::{
+    data::{
+        note::Note,
+        tracking::{
+            EditTrackingRequest, StartTrackingRequest, StopTrackingRequest, StopTrackingResponse,
+            TrackingResponse,
+        },
+    },
+    TimeularHttpClient,
+};
+use crate::Result;
+use chrono::{DateTime, Utc};
+
+impl TimeularHttpClient<'_> {
+    pub fn get_tracking(&self, token: String) -> Result<TrackingResponse> {
+        self.get(
+            token,
+            "/tracking",
+            "fetching the current tracking".to_owned(),
+        )
+    }
+
+    pub fn start_tracking(
+        &self,
+        token: String,
+        activity_id: &str,
+        started_at: DateTime<Utc>,
+    ) -> Result<TrackingResponse> {
+        self.post(
+            &format!("/tracking/{}/start", activity_id),
+            token,
+            &StartTrackingRequest { started_at },
+            "starting a tracking".to_owned(),
+        )
+    }
+
+    pub fn edit_tracking_note(&self, token: String, note: Note) -> Result<TrackingResponse> {
+        self.patch(
+            "/tracking",
+            token,
+            &EditTrackingRequest { note },
+            "editing the current tracking".to_owned(),
+        )
+    }
+
+    pub fn stop_tracking(
+        &self,
+        token: String,
+        stopped_at: DateTime<Utc>,
+    ) -> Result<StopTrackingResponse> {
+        self.post(
+            "/tracking/stop",
+            token,
+            &StopTrackingRequest { stopped_at },
+            "stopping a tracking".to_owned(),
+        )
+    }
+}
@@ -1,8 +1,27 @@
-use crate::{error::Error::AuthenticationInformationMissingError, Result};
+use crate::{
+    cache::{
+        time_entries_key, whole_days, Cache, KEY_ACTIVITIES, KEY_DEFAULT_SPACE,
+        KEY_TAGS_AND_MENTIONS, KEY_TIME_ENTRIES, KEY_TRACKING,
+    },
+    daemon::DaemonClient,
+    error::Error::{ApiUnreachableError, AuthenticationInformationMissingError, DaemonError},
+    util::color::random_color,
+    Result,
+};
+use chrono::{DateTime, Utc};
 use http::TimeularHttpClient;
-use rand::Rng;
+use serde::{de::DeserializeOwned, Serialize};
+use std::rc::Rc;
+
+pub use http::data::{
+    note::{Note, NoteReference},
+    time_entry::TimeEntryResponse,
+    tracking::CurrentTrackingResponse,
+    ActivityListResponse, ActivityResponse, TagResponse, TagsAndMentionsResponse,
+};
 
 mod http;
+mod switch;
 
 #[derive(Clone)]
 pub struct TimeularCredentials {
@@ -32,6 +51,9 @@ impl TimeularAuth {
 pub struct Timeular<'a> {
     client: TimeularHttpClient<'a>,
     auth_data: TimeularAuth,
+    cache: Option<Rc<Cache>>,
+    offline: bool,
+    daemon: Option<Rc<DaemonClient>>,
 }
 
 trait AuthenticatedCall {
@@ -61,9 +83,54 @@ impl Timeular<'_> {
         Ok(Timeular {
             client: tmlr_client,
             auth_data: auth,
+            cache: None,
+            offline: false,
+            daemon: None,
+        })
+    }
+
+    /// Creates a client which uses the session of the running daemon instead of logging in, fails
+    /// if the daemon is logged in with another API key
+    pub fn with_daemon(auth_data: TimeularAuth, daemon: DaemonClient) -> Result<Self> {
+        let token = daemon.token(&auth_data.credentials.api_key)?;
+        Ok(Timeular {
+            client: TimeularHttpClient::new(),
+            auth_data: TimeularAuth {
+                token: Some(token),
+                ..auth_data
+            },
+            cache: None,
+            offline: false,
+            daemon: Some(Rc::new(daemon)),
         })
     }
 
+    /// Creates a client without logging in, every API call fails as unreachable. Only cached
+    /// responses can be read.
+    pub fn offline(auth_data: TimeularAuth) -> Self {
+        Timeular {
+            client: TimeularHttpClient::new(),
+            auth_data: TimeularAuth {
+                token: None,
+                ..auth_data
+            },
+            cache: None,
+            offline: true,
+            daemon: None,
+        }
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Reads activities, tags, mentions, spaces and time entries through the cache, changes
+    /// invalidate the affected responses
+    pub fn with_cache(mut self, cache: Cache) -> Self {
+        self.cache = Some(Rc::new(cache));
+        self
+    }
+
     pub fn create_activity(
         &self,
         name: String,
@@ -75,16 +142,13 @@ impl Timeular<'_> {
                 let ac = self.client.create_activity(
                     v.to_owned(),
                     name.to_owned(),
-                    color.unwrap_or_else(|| create_random_color()),
+                    color.unwrap_or_else(random_color),
                     match space_id {
                         Some(v) => v,
-                        None => {
-                            let id = self.client.get_default_space_id(v.to_owned())?;
-                            log::debug!("No space provided - getting default space ({})", id);
-                            id
-                        }
+                        None => self.default_space_id()?,
                     },
                 )?;
+                self.invalidate(KEY_ACTIVITIES);
                 Ok((ac.id, ac.name))
             }
             None => Err(AuthenticationInformationMissingError),
@@ -96,7 +160,7 @@ impl Timeular<'_> {
         label: String,
         key: Option<String>,
         space_id: Option<String>,
-    ) -> Result<i64> {
+    ) -> Result<TagResponse> {
         match &self.auth_data.token {
             Some(v) => {
                 let tag = self.client.create_tag(
@@ -105,22 +169,357 @@ impl Timeular<'_> {
                     key,
                     match space_id {
                         Some(v) => v,
-                        None => {
-                            let id = self.client.get_default_space_id(v.to_owned())?;
-                            log::debug!("No space provided - getting default space ({})", id);
-                            id
-                        }
+                        None => self.default_space_id()?,
                     },
                 )?;
-                Ok(tag.id)
+                self.invalidate(KEY_TAGS_AND_MENTIONS);
+                Ok(tag)
             }
             None => Err(AuthenticationInformationMissingError),
         }
     }
+
+    pub fn edit_activity(
+        &self,
+        activity_id: &str,
+        name: Option<String>,
+        color: Option<String>,
+    ) -> Result<ActivityResponse> {
+        let activity = self
+            .client
+            .edit_activity(self.token()?, activity_id, name, color)?;
+        self.invalidate(KEY_ACTIVITIES);
+        Ok(activity)
+    }
+
+    pub fn archive_activity(&self, activity_id: &str) -> Result<()> {
+        self.client.archive_activity(self.token()?, activity_id)?;
+        self.invalidate(KEY_ACTIVITIES);
+        Ok(())
+    }
+
+    pub fn unarchive_activity(&self, activity_id: &str) -> Result<ActivityResponse> {
+        let activity = self.client.unarchive_activity(self.token()?, activity_id)?;
+        self.invalidate(KEY_ACTIVITIES);
+        Ok(activity)
+    }
+
+    pub fn assign_device_side(&self, activity_id: &str, side: u8) -> Result<ActivityResponse> {
+        let activity = self
+            .client
+            .assign_device_side(self.token()?, activity_id, side)?;
+        self.invalidate(KEY_ACTIVITIES);
+        Ok(activity)
+    }
+
+    pub fn unassign_device_side(&self, activity_id: &str, side: u8) -> Result<ActivityResponse> {
+        let activity = self
+            .client
+            .unassign_device_side(self.token()?, activity_id, side)?;
+        self.invalidate(KEY_ACTIVITIES);
+        Ok(activity)
+    }
+
+    pub fn edit_tag(
+        &self,
+        tag_id: i64,
+        label: Option<String>,
+        key: Option<String>,
+    ) -> Result<TagResponse> {
+        let tag = self.client.edit_tag(self.token()?, tag_id, label, key)?;
+        self.invalidate(KEY_TAGS_AND_MENTIONS);
+        Ok(tag)
+    }
+
+    pub fn edit_mention(
+        &self,
+        mention_id: i64,
+        label: Option<String>,
+        key: Option<String>,
+    ) -> Result<TagResponse> {
+        let mention = self
+            .client
+            .edit_mention(self.token()?, mention_id, label, key)?;
+        self.invalidate(KEY_TAGS_AND_MENTIONS);
+        Ok(mention)
+    }
+
+    pub fn list_activities(&self) -> Result<ActivityListResponse> {
+        self.cached(KEY_ACTIVITIES, || {
+            self.ask_daemon(DaemonClient::list_activities)
+                .map_or_else(|| self.client.list_activities(self.token()?), Ok)
+        })
+    }
+
+    pub fn list_tags_and_mentions(&self) -> Result<TagsAndMentionsResponse> {
+        self.cached(KEY_TAGS_AND_MENTIONS, || {
+            self.ask_daemon(DaemonClient::list_tags_and_mentions)
+                .map_or_else(|| self.client.list_tags_and_mentions(self.token()?), Ok)
+        })
+    }
+
+    fn default_space_id(&self) -> Result<String> {
+        self.cached(KEY_DEFAULT_SPACE, || {
+            let id = self.client.get_default_space_id(self.token()?)?;
+            log::debug!("No space provided - getting default space ({})", id);
+            Ok(id)
+        })
+    }
+
+    pub fn current_tracking(&self) -> Result<Option<CurrentTrackingResponse>> {
+        if let Some(tracking) = self.ask_daemon(DaemonClient::current_tracking) {
+            return Ok(tracking);
+        }
+        let tracking = self.client.get_tracking(self.token()?)?.current_tracking;
+        if let Some(c) = &self.cache {
+            c.put(KEY_TRACKING, &tracking);
+        }
+        Ok(tracking)
+    }
+
+    pub fn start_tracking(
+        &self,
+        activity_id: &str,
+        note: Option<Note>,
+    ) -> Result<Option<CurrentTrackingResponse>> {
+        self.start_tracking_at(activity_id, Utc::now(), note)
+    }
+
+    pub fn start_tracking_at(
+        &self,
+        activity_id: &str,
+        started_at: DateTime<Utc>,
+        note: Option<Note>,
+    ) -> Result<Option<CurrentTrackingResponse>> {
+        let token = self.token()?;
+        let mut tracking = self
+            .client
+            .start_tracking(token.to_owned(), activity_id, started_at)?;
+
+        if let Some(n) = note.filter(|n| !n.is_empty()) {
+            log::debug!("Adding note to the started tracking");
+            // The tracking is running anyway, so a missing note doesn't fail the start
+            match self.client.edit_tracking_note(token, n) {
+                Ok(v) => tracking = v,
+                Err(e) => log::warn!("The note could not be added to the tracking: {}", e),
+            }
+        }
+        self.update_tracking(&tracking.current_tracking);
+        Ok(tracking.current_tracking)
+    }
+
+    /// Stops the current tracking and starts the given activity at exactly the same time. If the
+    /// start fails the stopped tracking is restored, so neither a gap nor a time entry is left
+    /// behind. Returns the time entry of the stopped tracking.
+    pub fn switch_tracking(
+        &self,
+        activity_id: &str,
+        note: Option<Note>,
+    ) -> Result<Option<TimeEntryResponse>> {
+        switch::switch(self, activity_id, note, Utc::now())
+    }
+
+    pub fn list_time_entries(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<TimeEntryResponse>> {
+        // Whole days are fetched, so the response is reused by ranges ending at "now"
+        let (day_from, day_to) = whole_days(from, to);
+        let entries: Vec<TimeEntryResponse> =
+            self.cached(&time_entries_key(day_from, day_to), || {
+                Ok(self
+                    .client
+                    .list_time_entries(self.token()?, day_from, day_to)?
+                    .time_entries)
+            })?;
+        Ok(entries
+            .into_iter()
+            .filter(|e| e.duration.started_at <= to && e.duration.stopped_at >= from)
+            .collect())
+    }
+
+    /// Like [`Timeular::list_time_entries`], but bypasses the cache, for decisions which must
+    /// not miss entries created elsewhere in the meantime
+    pub fn fetch_time_entries(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<TimeEntryResponse>> {
+        Ok(self
+            .client
+            .list_time_entries(self.token()?, from, to)?
+            .time_entries)
+    }
+
+    pub fn create_time_entry(
+        &self,
+        activity_id: &str,
+        started_at: DateTime<Utc>,
+        stopped_at: DateTime<Utc>,
+        note: Note,
+    ) -> Result<TimeEntryResponse> {
+        let entry = self.client.create_time_entry(
+            self.token()?,
+            activity_id.to_owned(),
+            started_at,
+            stopped_at,
+            note,
+        )?;
+        self.invalidate(KEY_TIME_ENTRIES);
+        Ok(entry)
+    }
+
+    pub fn get_time_entry(&self, time_entry_id: &str) -> Result<TimeEntryResponse> {
+        self.client.get_time_entry(self.token()?, time_entry_id)
+    }
+
+    pub fn edit_time_entry(
+        &self,
+        time_entry_id: &str,
+        activity_id: &str,
+        started_at: DateTime<Utc>,
+        stopped_at: DateTime<Utc>,
+        note: Note,
+    ) -> Result<TimeEntryResponse> {
+        let entry = self.client.edit_time_entry(
+            self.token()?,
+            time_entry_id,
+            activity_id.to_owned(),
+            started_at,
+            stopped_at,
+            note,
+        )?;
+        self.invalidate(KEY_TIME_ENTRIES);
+        Ok(entry)
+    }
+
+    pub fn delete_time_entry(&self, time_entry_id: &str) -> Result<()> {
+        self.client
+            .delete_time_entry(self.token()?, time_entry_id)?;
+        self.invalidate(KEY_TIME_ENTRIES);
+        Ok(())
+    }
+
+    pub fn stop_tracking(&self) -> Result<TimeEntryResponse> {
+        self.stop_tracking_at(Utc::now())
+    }
+
+    pub fn stop_tracking_at(&self, stopped_at: DateTime<Utc>) -> Result<TimeEntryResponse> {
+        let entry = self
+            .client
+            .stop_tracking(self.token()?, stopped_at)?
+            .created_time_entry;
+        self.invalidate(KEY_TIME_ENTRIES);
+        self.update_tracking(&None);
+        Ok(entry)
+    }
+
+    /// Returns the cached value or fetches and caches it
+    fn cached<T: Serialize + DeserializeOwned>(
+        &self,
+        key: &str,
+        fetch: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        if let Some(v) = self.cache.as_ref().and_then(|c| c.get(key)) {
+            log::debug!("Using cached {}", key);
+            return Ok(v);
+        }
+
+        let value = fetch()?;
+        if let Some(c) = &self.cache {
+            c.put(key, &value);
+        }
+        Ok(value)
+    }
+
+    fn invalidate(&self, prefix: &str) {
+        if let Some(c) = &self.cache {
+            c.invalidate(prefix);
+        }
+        if let Some(d) = &self.daemon {
+            if let Err(e) = d.invalidate(prefix) {
+                log::debug!("Unable to invalidate {} in the daemon: {}", prefix, e);
+            }
+        }
+    }
+
+    /// Stores the changed tracking, so local readers like the shell prompt don't have to ask the
+    /// API
+    fn update_tracking(&self, tracking: &Option<CurrentTrackingResponse>) {
+        if let Some(c) = &self.cache {
+            c.put(KEY_TRACKING, tracking);
+        }
+        if let Some(d) = &self.daemon {
+            if let Err(e) = d.invalidate(KEY_TRACKING) {
+                log::debug!("Unable to invalidate {} in the daemon: {}", KEY_TRACKING, e);
+            }
+        }
+    }
+
+    /// Asks the daemon, `None` if there is none or it failed to answer
+    fn ask_daemon<T>(&self, call: impl FnOnce(&DaemonClient) -> Result<T>) -> Option<T> {
+        let daemon = self.daemon.as_ref()?;
+        call(daemon)
+            .map_err(|e| log::debug!("Daemon failed, using the API: {}", e))
+            .ok()
+    }
+
+    /// The token of the session, handed out by the daemon to clients of the same account only
+    pub fn session_token(&self, api_key: &str) -> Result<String> {
+        if api_key != self.auth_data.credentials.api_key {
+            return Err(DaemonError(
+                "the daemon is logged in with another API key".to_owned(),
+            ));
+        }
+        self.token()
+    }
+
+    fn token(&self) -> Result<String> {
+        if self.offline {
+            return Err(ApiUnreachableError("working offline".to_owned()));
+        }
+        self.auth_data
+            .token
+            .to_owned()
+            .ok_or(AuthenticationInformationMissingError)
+    }
+}
+
+impl switch::Steps for Timeular<'_> {
+    fn running(&self) -> Result<Option<CurrentTrackingResponse>> {
+        Ok(self.client.get_tracking(self.token()?)?.current_tracking)
+    }
+
+    fn stop(&self, at: DateTime<Utc>) -> Result<TimeEntryResponse> {
+        self.stop_tracking_at(at)
+    }
+
+    fn start(&self, activity_id: &str, at: DateTime<Utc>, note: Option<Note>) -> Result<()> {
+        self.start_tracking_at(activity_id, at, note).map(|_| ())
+    }
+
+    fn delete_entry(&self, entry: &TimeEntryResponse) -> Result<()> {
+        self.delete_time_entry(&entry.id)
+    }
+
+    fn create_entry(&self, entry: &TimeEntryResponse) -> Result<()> {
+        self.create_time_entry(
+            &entry.activity_id,
+            entry.duration.started_at,
+            entry.duration.stopped_at,
+            entry.note.clone().unwrap_or_default(),
+        )
+        .map(|_| ())
+    }
 }
 
 impl Drop for Timeular<'_> {
     fn drop(&mut self) {
+        // The session belongs to the daemon
+        if self.daemon.is_some() {
+            return;
+        }
         if let Some(t) = &self.auth_data.token {
             log::debug!("Releasing authentication token.");
             if let Err(e) = self.client.logout(t) {
@@ -129,13 +528,3 @@ impl Drop for Timeular<'_> {
         }
     }
 }
-
-fn create_random_color() -> String {
-    let mut gen = rand::thread_rng();
-    let r = gen.gen_range(0..255);
-    let g = gen.gen_range(0..255);
-    let b = gen.gen_range(0..255);
-    let color = format!("#{:02x}{:02x}{:02x}", r, g, b);
-    log::debug!("Generating random color {}", color);
-    color
-}
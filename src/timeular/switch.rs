@@ -0,0 +1,191 @@
+//! Switching the tracking is made of several API calls, if one of them fails the previous
+//! tracking is restored, so neither a gap nor a stray time entry is left behind
+
+use super::{CurrentTrackingResponse, Note, TimeEntryResponse};
+use crate::Result;
+use chrono::{DateTime, Utc};
+
+/// The calls a switch is made of
+pub(super) trait Steps {
+    fn running(&self) -> Result<Option<CurrentTrackingResponse>>;
+    fn stop(&self, at: DateTime<Utc>) -> Result<TimeEntryResponse>;
+    fn start(&self, activity_id: &str, at: DateTime<Utc>, note: Option<Note>) -> Result<()>;
+    fn delete_entry(&self, entry: &TimeEntryResponse) -> Result<()>;
+    fn create_entry(&self, entry: &TimeEntryResponse) -> Result<()>;
+}
+
+/// Stops the running tracking and starts the activity at the same time, returns the time entry
+/// of the stopped tracking
+pub(super) fn switch(
+    steps: &impl Steps,
+    activity_id: &str,
+    note: Option<Note>,
+    at: DateTime<Utc>,
+) -> Result<Option<TimeEntryResponse>> {
+    let stopped = match steps.running()? {
+        Some(previous) => Some((previous, steps.stop(at)?)),
+        None => None,
+    };
+
+    if let Err(e) = steps.start(activity_id, at, note) {
+        if let Some((previous, entry)) = &stopped {
+            let is_running = steps.running().map(|t| t.is_some()).unwrap_or(false);
+            if !is_running {
+                restore(steps, previous, entry);
+            }
+        }
+        return Err(e);
+    }
+    Ok(stopped.map(|(_, entry)| entry))
+}
+
+/// Turns the time entry back into the running tracking. The entry has to be deleted first, as it
+/// covers the time of the tracking. If the tracking can't be started again the entry is created
+/// again, so the tracked time is kept.
+fn restore(steps: &impl Steps, previous: &CurrentTrackingResponse, entry: &TimeEntryResponse) {
+    log::info!("Restoring the previous tracking.");
+    if let Err(e) = steps.delete_entry(entry) {
+        log::error!("Unable to restore the previous tracking: {}", e);
+        return;
+    }
+
+    let restarted = steps.start(
+        &previous.activity_id,
+        previous.started_at,
+        previous.note.clone(),
+    );
+    if let Err(e) = restarted {
+        log::error!("Unable to restore the previous tracking: {}", e);
+        if let Err(e) = steps.create_entry(entry) {
+            log::error!(
+                "Unable to create the time entry of the previous tracking again: {}",
+                e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error::ApiUnreachableError;
+    use chrono::TimeZone;
+    use std::cell::RefCell;
+
+    /// Records the calls, the calls listed in `failing` fail
+    #[derive(Default)]
+    struct FakeSteps {
+        running: RefCell<Option<CurrentTrackingResponse>>,
+        failing: Vec<&'static str>,
+        calls: RefCell<Vec<String>>,
+    }
+
+    impl FakeSteps {
+        fn call(&self, call: String) -> Result<()> {
+            let failed = self.failing.contains(&call.as_str());
+            self.calls.borrow_mut().push(call);
+            if failed {
+                return Err(ApiUnreachableError("failing".to_owned()));
+            }
+            Ok(())
+        }
+    }
+
+    impl Steps for FakeSteps {
+        fn running(&self) -> Result<Option<CurrentTrackingResponse>> {
+            Ok(self.running.borrow().clone())
+        }
+
+        fn stop(&self, at: DateTime<Utc>) -> Result<TimeEntryResponse> {
+            self.call("stop".to_owned())?;
+            let previous = self
+                .running
+                .borrow_mut()
+                .take()
+                .expect("A tracking is running");
+            Ok(TimeEntryResponse::new(
+                "10",
+                &previous.activity_id,
+                previous.started_at,
+                at,
+            ))
+        }
+
+        fn start(&self, activity_id: &str, at: DateTime<Utc>, _: Option<Note>) -> Result<()> {
+            self.call(format!("start {}", activity_id))?;
+            *self.running.borrow_mut() = Some(tracking(activity_id, at));
+            Ok(())
+        }
+
+        fn delete_entry(&self, entry: &TimeEntryResponse) -> Result<()> {
+            self.call(format!("delete {}", entry.id))
+        }
+
+        fn create_entry(&self, entry: &TimeEntryResponse) -> Result<()> {
+            self.call(format!("create {}", entry.activity_id))
+        }
+    }
+
+    fn tracking(activity_id: &str, started_at: DateTime<Utc>) -> CurrentTrackingResponse {
+        CurrentTrackingResponse {
+            activity_id: activity_id.to_owned(),
+            started_at,
+            note: None,
+        }
+    }
+
+    /// Switches from the tracking of activity 1 to activity 2
+    fn run_switch(failing: Vec<&'static str>) -> (Result<Option<TimeEntryResponse>>, FakeSteps) {
+        let steps = FakeSteps {
+            running: RefCell::new(Some(tracking("1", Utc.ymd(2021, 6, 1).and_hms(9, 0, 0)))),
+            failing,
+            ..FakeSteps::default()
+        };
+        let result = switch(&steps, "2", None, Utc.ymd(2021, 6, 1).and_hms(10, 0, 0));
+        (result, steps)
+    }
+
+    #[test]
+    fn test_switch() {
+        let (result, steps) = run_switch(vec![]);
+        assert_eq!(result.unwrap().unwrap().activity_id, "1");
+        assert_eq!(*steps.calls.borrow(), vec!["stop", "start 2"]);
+        assert_eq!(steps.running.borrow().as_ref().unwrap().activity_id, "2");
+    }
+
+    #[test]
+    fn test_switch_restores_previous_tracking() {
+        let (result, steps) = run_switch(vec!["start 2"]);
+        assert!(result.is_err());
+        assert_eq!(
+            *steps.calls.borrow(),
+            vec!["stop", "start 2", "delete 10", "start 1"]
+        );
+        assert_eq!(steps.running.borrow().as_ref().unwrap().activity_id, "1");
+    }
+
+    #[test]
+    fn test_switch_creates_entry_again_if_restart_fails() {
+        let (result, steps) = run_switch(vec!["start 2", "start 1"]);
+        assert!(result.is_err());
+        assert_eq!(
+            *steps.calls.borrow(),
+            vec!["stop", "start 2", "delete 10", "start 1", "create 1"]
+        );
+    }
+
+    #[test]
+    fn test_switch_keeps_entry_if_delete_fails() {
+        let (result, steps) = run_switch(vec!["start 2", "delete 10"]);
+        assert!(result.is_err());
+        assert_eq!(*steps.calls.borrow(), vec!["stop", "start 2", "delete 10"]);
+    }
+
+    #[test]
+    fn test_switch_without_tracking() {
+        let steps = FakeSteps::default();
+        let at = Utc.ymd(2021, 6, 1).and_hms(10, 0, 0);
+        assert!(switch(&steps, "2", None, at).unwrap().is_none());
+        assert_eq!(*steps.calls.borrow(), vec!["start 2"]);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{error::Error::InvalidDateError, Result};
+use chrono::{Duration, NaiveDate};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const ICS_DATE_FORMAT: &str = "%Y%m%d";
+const RANGE_SEPARATOR: &str = "..";
+
+/// Parses a date like `2021-12-24`
+pub fn parse_date(input: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(input.trim(), DATE_FORMAT)
+        .map_err(|_| InvalidDateError(input.to_owned()))
+}
+
+/// Parses dates and inclusive ranges like `2021-08-02..2021-08-13` into single days
+pub fn parse_days(values: &[String]) -> Result<Vec<NaiveDate>> {
+    let mut days = Vec::new();
+    for value in values {
+        match value.split_once(RANGE_SEPARATOR) {
+            Some((from, to)) => {
+                let (from, to) = (parse_date(from)?, parse_date(to)?);
+                if from > to {
+                    return Err(InvalidDateError(value.to_owned()));
+                }
+                days.extend(from.iter_days().take_while(|d| *d <= to));
+            }
+            None => days.push(parse_date(value)?),
+        }
+    }
+    Ok(days)
+}
+
+/// Reads the days of all events of an iCalendar file. Only the date part of `DTSTART` and
+/// `DTEND` is used, the end of an event is exclusive like in all-day events.
+pub fn parse_ics(content: &str) -> Vec<NaiveDate> {
+    let mut days = Vec::new();
+    let mut start = None;
+    let mut end = None;
+
+    for line in content.lines().map(str::trim) {
+        if line.eq_ignore_ascii_case("BEGIN:VEVENT") {
+            start = None;
+            end = None;
+        } else if line.starts_with("DTSTART") {
+            start = ics_date(line);
+        } else if line.starts_with("DTEND") {
+            end = ics_date(line);
+        } else if line.eq_ignore_ascii_case("END:VEVENT") {
+            if let Some(from) = start {
+                let to = end
+                    .filter(|e| *e > from)
+                    .unwrap_or(from + Duration::days(1));
+                days.extend(from.iter_days().take_while(|d| *d < to));
+            }
+        }
+    }
+    days
+}
+
+fn ics_date(line: &str) -> Option<NaiveDate> {
+    let value = line.rsplit(':').next()?;
+    NaiveDate::parse_from_str(value.get(..8)?, ICS_DATE_FORMAT).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_days() {
+        let days =
+            parse_days(&["2021-12-24".to_owned(), "2021-12-30..2022-01-02".to_owned()]).unwrap();
+        assert_eq!(days.len(), 5);
+        assert_eq!(days[1], NaiveDate::from_ymd(2021, 12, 30));
+        assert_eq!(days[4], NaiveDate::from_ymd(2022, 1, 2));
+
+        assert!(parse_days(&["24.12.2021".to_owned()]).is_err());
+        assert!(parse_days(&["2022-01-02..2021-12-30".to_owned()]).is_err());
+    }
+
+    #[test]
+    fn test_parse_ics() {
+        let content = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART;VALUE=DATE:20211225\r\n\
+            DTEND;VALUE=DATE:20211227\r\n\
+            SUMMARY:Christmas\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART:20220101T000000Z\r\n\
+            SUMMARY:New Year\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        assert_eq!(
+            parse_ics(content),
+            vec![
+                NaiveDate::from_ymd(2021, 12, 25),
+                NaiveDate::from_ymd(2021, 12, 26),
+                NaiveDate::from_ymd(2022, 1, 1)
+            ]
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{error::Error::GitError, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Runs git in the directory and returns the trimmed output
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| GitError(e.to_string()))?;
+    if !output.status.success() {
+        return Err(GitError(
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// Returns the checked out branch, `None` for a detached HEAD
+pub fn current_branch(dir: &Path) -> Result<Option<String>> {
+    git(dir, &["rev-parse", "--git-dir"])?;
+    Ok(git(dir, &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok())
+}
+
+/// Returns the directory of the hooks, which respects `core.hooksPath`
+pub fn hooks_dir(dir: &Path) -> Result<PathBuf> {
+    let path = PathBuf::from(git(dir, &["rev-parse", "--git-path", "hooks"])?);
+    Ok(if path.is_absolute() {
+        path
+    } else {
+        dir.join(path)
+    })
+}
+
+/// Returns the commit time of HEAD, `None` if there is no commit yet
+pub fn head_commit_time(dir: &Path) -> Result<Option<DateTime<Utc>>> {
+    git(dir, &["rev-parse", "--git-dir"])?;
+    Ok(git(dir, &["log", "-1", "--format=%ct"])
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .map(|ts| DateTime::from_utc(NaiveDateTime::from_timestamp(ts, 0), Utc)))
+}
+
+/// Returns the URLs of all remotes
+pub fn remote_urls(dir: &Path) -> Result<Vec<String>> {
+    let remotes = git(dir, &["remote"])?;
+    Ok(remotes
+        .lines()
+        .filter_map(|r| git(dir, &["remote", "get-url", r]).ok())
+        .collect())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Creates an empty repository in a fresh temporary directory
+    pub fn init_repo(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tmlr-git-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        git(&dir, &["init", "--quiet"]).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_repository_info() {
+        let dir = init_repo("info");
+        git(&dir, &["checkout", "--quiet", "-b", "PROJ-123-login"]).unwrap();
+        assert_eq!(
+            current_branch(&dir).unwrap().as_deref(),
+            Some("PROJ-123-login")
+        );
+        assert_eq!(head_commit_time(&dir).unwrap(), None);
+        assert!(hooks_dir(&dir).unwrap().ends_with(".git/hooks"));
+
+        git(
+            &dir,
+            &["remote", "add", "origin", "git@example.com:acme/app.git"],
+        )
+        .unwrap();
+        assert_eq!(
+            remote_urls(&dir).unwrap(),
+            vec!["git@example.com:acme/app.git"]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,3 +1,7 @@
 //! Shared utilities module
 
+pub mod calendar;
+pub mod color;
+pub mod git;
 pub mod logging;
+pub mod time;
@@ -0,0 +1,173 @@
+use crate::{error::Error::InvalidTimeError, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+const DATE_TIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+];
+const TIME_FORMATS: [&str; 2] = ["%H:%M", "%H:%M:%S"];
+
+/// Parses a duration like `15m`, `1h30m`, `2d` or an ISO 8601 duration like `PT15M`.
+/// A leading `+` or `-` defines the sign of the duration.
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let value = input.trim();
+    let (negative, value) = match value.chars().next() {
+        Some('-') => (true, &value[1..]),
+        Some('+') => (false, &value[1..]),
+        _ => (false, value),
+    };
+    if value.is_empty() {
+        return Err(InvalidTimeError(input.to_owned()));
+    }
+
+    let upper = value.to_uppercase();
+    let iso = if upper.starts_with('P') {
+        upper
+    } else {
+        match upper.find('D') {
+            Some(idx) => format!("P{}T{}", &upper[..=idx], &upper[idx + 1..]),
+            None => format!("PT{}", upper),
+        }
+    };
+
+    let duration = time_parse::duration::parse(&iso)
+        .ok()
+        .and_then(|d| Duration::from_std(d).ok())
+        .ok_or_else(|| InvalidTimeError(input.to_owned()))?;
+
+    Ok(if negative { -duration } else { duration })
+}
+
+/// Parses a point in time, relative to `now`. Supported are:
+/// - `now`
+/// - a relative duration like `-15m` or `+1h` (see [`parse_duration`])
+/// - a local time of today like `09:30`
+/// - a local date and time like `2021-06-01 09:30`
+/// - an RFC 3339 timestamp like `2021-06-01T09:30:00+02:00`
+pub fn parse_time(input: &str, now: DateTime<Local>) -> Result<DateTime<Utc>> {
+    parse_time_on(input, now, now.date().naive_local())
+}
+
+/// Like [`parse_time`], but a bare time like `09:30` is taken on the given day
+pub fn parse_time_on(input: &str, now: DateTime<Local>, day: NaiveDate) -> Result<DateTime<Utc>> {
+    let value = input.trim();
+
+    if value.eq_ignore_ascii_case("now") {
+        return Ok(now.with_timezone(&Utc));
+    }
+
+    if value.starts_with('-') || value.starts_with('+') {
+        return Ok((now + parse_duration(value)?).with_timezone(&Utc));
+    }
+
+    if let Ok(v) = DateTime::parse_from_rfc3339(value) {
+        return Ok(v.with_timezone(&Utc));
+    }
+
+    let naive = DATE_TIME_FORMATS
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(value, f).ok())
+        .or_else(|| {
+            TIME_FORMATS
+                .iter()
+                .find_map(|f| NaiveTime::parse_from_str(value, f).ok())
+                .map(|t| day.and_time(t))
+        })
+        .ok_or_else(|| InvalidTimeError(input.to_owned()))?;
+
+    local_to_utc(naive).ok_or_else(|| InvalidTimeError(input.to_owned()))
+}
+
+pub fn local_to_utc(naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|v| v.with_timezone(&Utc))
+}
+
+/// Returns the start of the local day, which is after midnight if the clocks skip it
+pub fn start_of_day(day: NaiveDate) -> DateTime<Utc> {
+    (0..24)
+        .find_map(|h| local_to_utc(day.and_hms(h, 0, 0)))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&day.and_hms(0, 0, 0)))
+}
+
+/// Formats a duration as `1h 05m`
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes();
+    let sign = if minutes < 0 { "-" } else { "" };
+    format!("{}{}h {:02}m", sign, minutes.abs() / 60, minutes.abs() % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("15m").unwrap(), Duration::minutes(15));
+        assert_eq!(parse_duration("+1h30m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("-45s").unwrap(), Duration::seconds(-45));
+        assert_eq!(parse_duration("2d").unwrap(), Duration::days(2));
+        assert_eq!(parse_duration("1d2h").unwrap(), Duration::hours(26));
+        assert_eq!(parse_duration("PT5M").unwrap(), Duration::minutes(5));
+        assert!(parse_duration("abc").is_err());
+        assert!(parse_duration("").is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let now = Local.ymd(2021, 6, 1).and_hms(12, 0, 0);
+
+        assert_eq!(parse_time("now", now).unwrap(), now.with_timezone(&Utc));
+        assert_eq!(
+            parse_time("-15m", now).unwrap(),
+            Local.ymd(2021, 6, 1).and_hms(11, 45, 0).with_timezone(&Utc)
+        );
+        assert_eq!(
+            parse_time("09:30", now).unwrap(),
+            Local.ymd(2021, 6, 1).and_hms(9, 30, 0).with_timezone(&Utc)
+        );
+        assert_eq!(
+            parse_time("2021-05-31 17:05", now).unwrap(),
+            Local.ymd(2021, 5, 31).and_hms(17, 5, 0).with_timezone(&Utc)
+        );
+        assert_eq!(
+            parse_time("2021-05-31T17:05:00Z", now).unwrap(),
+            Utc.ymd(2021, 5, 31).and_hms(17, 5, 0)
+        );
+        assert!(parse_time("yesterday", now).is_err());
+    }
+
+    #[test]
+    fn test_parse_time_on() {
+        let now = Local.ymd(2021, 6, 1).and_hms(12, 0, 0);
+        let day = NaiveDate::from_ymd(2021, 5, 28);
+
+        assert_eq!(
+            parse_time_on("09:30", now, day).unwrap(),
+            Local.ymd(2021, 5, 28).and_hms(9, 30, 0).with_timezone(&Utc)
+        );
+        assert_eq!(
+            parse_time_on("-15m", now, day).unwrap(),
+            Local.ymd(2021, 6, 1).and_hms(11, 45, 0).with_timezone(&Utc)
+        );
+    }
+
+    #[test]
+    fn test_start_of_day() {
+        assert_eq!(
+            start_of_day(NaiveDate::from_ymd(2021, 6, 1)),
+            Local.ymd(2021, 6, 1).and_hms(0, 0, 0).with_timezone(&Utc)
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::minutes(65)), "1h 05m");
+        assert_eq!(format_duration(Duration::minutes(-90)), "-1h 30m");
+        assert_eq!(format_duration(Duration::seconds(59)), "0h 00m");
+    }
+}